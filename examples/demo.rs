@@ -8,7 +8,7 @@ fn main() {
             DefaultPlugins,
             bevy::diagnostic::LogDiagnosticsPlugin::default(),
             bevy_framepace::FramepacePlugin,
-            bevy_framepace::debug::DiagnosticsPlugin,
+            bevy_framepace::debug::DiagnosticsPlugin::default(),
         ))
         .add_systems(Startup, setup)
         .add_systems(Update, (toggle_plugin, update_ui, update_cursor))
@@ -27,7 +27,8 @@ fn toggle_plugin(
         settings.limiter = match settings.limiter {
             Limiter::Auto => Limiter::Off,
             Limiter::Off => Limiter::from_framerate(30.0),
-            Limiter::Manual(_) => Limiter::Auto,
+            Limiter::Manual(_) => Limiter::Adaptive(Default::default()),
+            _ => Limiter::Auto,
         }
     }
 }
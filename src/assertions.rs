@@ -0,0 +1,110 @@
+//! Opt-in assertions that verify framepacing accuracy on the current hardware.
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_time::prelude::*;
+use std::time::Duration;
+
+use crate::{FramepaceSettings, Limiter};
+
+/// Runs the app at a fixed frametime for a number of frames, then checks that the measured mean
+/// frametime is within a tolerance of the target.
+///
+/// A pass/fail summary is logged once the measurement completes. On failure, the app panics if
+/// [`PacingAssertionPlugin::panic_on_failure`] is set. If [`PacingAssertionPlugin::exit`] is set,
+/// an [`AppExit`] is sent afterwards, making this usable as a one-line check in CI.
+#[derive(Debug, Clone, Resource)]
+pub struct PacingAssertionPlugin {
+    /// The frametime the app is limited to during the measurement.
+    pub frametime: Duration,
+    /// The number of frames to measure.
+    pub frames: u32,
+    /// The number of frames to skip before measuring, to ignore startup hitches.
+    pub warmup_frames: u32,
+    /// The allowed deviation of the mean frametime from the target, as a fraction of the target.
+    pub tolerance: f64,
+    /// Panic if the measured mean frametime is outside of the tolerance.
+    pub panic_on_failure: bool,
+    /// Exit the app once the measurement completes.
+    pub exit: bool,
+}
+
+impl Default for PacingAssertionPlugin {
+    fn default() -> Self {
+        PacingAssertionPlugin {
+            frametime: Duration::from_secs_f64(1.0 / 30.0),
+            frames: 300,
+            warmup_frames: 30,
+            tolerance: 0.05,
+            panic_on_failure: true,
+            exit: true,
+        }
+    }
+}
+
+impl Plugin for PacingAssertionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.clone())
+            .add_systems(Startup, Self::apply_limiter)
+            .add_systems(Update, Self::assertion_system);
+    }
+}
+
+impl PacingAssertionPlugin {
+    fn apply_limiter(mut settings: ResMut<FramepaceSettings>, config: Res<PacingAssertionPlugin>) {
+        settings.limiter = Limiter::Manual(config.frametime);
+    }
+
+    /// Measures frametimes, and reports the result once enough frames have been measured.
+    pub fn assertion_system(
+        time: Res<Time<Real>>,
+        config: Res<PacingAssertionPlugin>,
+        mut measured: Local<(u32, Duration)>,
+        mut exit: EventWriter<AppExit>,
+    ) {
+        let (frame, total) = &mut *measured;
+        *frame += 1;
+        if *frame <= config.warmup_frames {
+            return;
+        }
+        *total += time.delta();
+        if *frame != config.warmup_frames + config.frames.max(1) {
+            return;
+        }
+
+        let mean = *total / config.frames.max(1);
+        let deviation = (mean.as_secs_f64() - config.frametime.as_secs_f64()).abs()
+            / config.frametime.as_secs_f64();
+        let passed = deviation <= config.tolerance;
+        if passed {
+            bevy_log::info!(
+                "Framepace assertion passed: mean frametime {:?}, target {:?}, deviation {:.2}%",
+                mean,
+                config.frametime,
+                deviation * 100.0
+            );
+        } else {
+            bevy_log::error!(
+                "Framepace assertion failed: mean frametime {:?}, target {:?}, deviation {:.2}% exceeds {:.2}%",
+                mean,
+                config.frametime,
+                deviation * 100.0,
+                config.tolerance * 100.0
+            );
+            if config.panic_on_failure {
+                panic!(
+                    "framepacing deviated from the target by {:.2}%",
+                    deviation * 100.0
+                );
+            }
+        }
+
+        if config.exit {
+            exit.send(if passed {
+                AppExit::Success
+            } else {
+                AppExit::error()
+            });
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Run conditions for scoping frame limiting to parts of an app.
+//!
+//! These compose with bevy's run condition combinators, and are meant to drive
+//! [`FramepaceSettings::enabled`] or [`FramepaceActivity`] from a system, e.g. to only limit the
+//! framerate in gameplay while a window is focused:
+//!
+//! ```ignore
+//! fn enable_limiter(mut settings: ResMut<FramepaceSettings>) {
+//!     if !settings.enabled {
+//!         settings.enabled = true;
+//!     }
+//! }
+//!
+//! fn disable_limiter(mut settings: ResMut<FramepaceSettings>) {
+//!     if settings.enabled {
+//!         settings.enabled = false;
+//!     }
+//! }
+//!
+//! app.add_systems(
+//!     Update,
+//!     (
+//!         enable_limiter.run_if(in_state(GameState::Playing).and(any_window_focused)),
+//!         disable_limiter.run_if(not(in_state(GameState::Playing).and(any_window_focused))),
+//!     ),
+//! );
+//! ```
+//!
+//! Only writing the settings when they need to change avoids triggering change detection, so the
+//! applied limiter is not recomputed every frame.
+
+use bevy_ecs::prelude::*;
+use bevy_window::prelude::*;
+
+#[cfg(doc)]
+use crate::FramepaceActivity;
+use crate::{EffectiveState, FramepaceSettings};
+
+/// Returns `true` if [`FramepaceSettings::enabled`] is set and the configured limiter is enabled.
+pub fn limiter_enabled(settings: Res<FramepaceSettings>) -> bool {
+    settings.enabled && settings.limiter.is_enabled()
+}
+
+/// Returns `true` if the plugin currently limits the framerate, see [`EffectiveState`].
+pub fn limiting(state: Res<EffectiveState>) -> bool {
+    state.is_limiting()
+}
+
+/// Returns `true` if any window has focus.
+pub fn any_window_focused(windows: Query<&Window>) -> bool {
+    windows.iter().any(|window| window.focused)
+}
+
+/// Returns `true` if any window is visible. Note that a visible window may still be minimized or
+/// occluded by other windows.
+pub fn any_window_visible(windows: Query<&Window>) -> bool {
+    windows.iter().any(|window| window.visible)
+}
@@ -4,41 +4,318 @@ use bevy_app::prelude::*;
 use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
 use bevy_ecs::prelude::*;
 use bevy_time::prelude::*;
+use std::time::Duration;
+
+use crate::Limiter;
 
 /// Adds [`Diagnostics`] data from `bevy_framepace`
-pub struct DiagnosticsPlugin;
+///
+/// By default, measurements are recorded to [`DiagnosticsPlugin::FRAMEPACE_FRAMETIME`],
+/// [`DiagnosticsPlugin::FRAMEPACE_OVERSLEEP`], [`DiagnosticsPlugin::FRAMEPACE_LIMITER_STATE`],
+/// [`DiagnosticsPlugin::FRAMEPACE_FRAME_REPEATS`], [`DiagnosticsPlugin::FRAMEPACE_FRAME_SKIPS`],
+/// and [`DiagnosticsPlugin::FRAMEPACE_WORK_TIME`].
+/// Use [`DiagnosticsPlugin::with_prefix`] or set the
+/// paths directly to disambiguate them from other diagnostic sources.
+///
+/// The measurements jitter from frame to frame. For a stable readout, e.g. an fps counter, use
+/// [`Diagnostic::smoothed`] rather than [`Diagnostic::value`], which averages over the last
+/// [`DiagnosticsPlugin::history_length`] measurements.
+#[derive(Debug, Clone, Resource)]
+pub struct DiagnosticsPlugin {
+    /// [`DiagnosticPath`] the frametime is recorded to.
+    pub frametime: DiagnosticPath,
+    /// [`DiagnosticPath`] the oversleep is recorded to.
+    pub oversleep: DiagnosticPath,
+    /// [`DiagnosticPath`] the limiter state is recorded to.
+    pub limiter_state: DiagnosticPath,
+    /// [`DiagnosticPath`] the frame repeats are recorded to.
+    pub frame_repeats: DiagnosticPath,
+    /// [`DiagnosticPath`] the frame skips are recorded to.
+    pub frame_skips: DiagnosticPath,
+    /// [`DiagnosticPath`] the work time is recorded to.
+    pub work_time: DiagnosticPath,
+    /// The units the frametime is recorded in.
+    pub frametime_units: FrametimeUnits,
+    /// The number of measurements kept by the frametime, oversleep, and work time diagnostics.
+    pub history_length: usize,
+}
+
+/// The units [`DiagnosticsPlugin::frametime`] is recorded in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FrametimeUnits {
+    /// Records the frametime in milliseconds.
+    #[default]
+    Millis,
+    /// Records the framerate in frames per second, the reciprocal of the whole frame, including
+    /// the time the limiter slept.
+    Fps,
+}
+
+impl Default for DiagnosticsPlugin {
+    fn default() -> Self {
+        DiagnosticsPlugin {
+            frametime: Self::FRAMEPACE_FRAMETIME,
+            oversleep: Self::FRAMEPACE_OVERSLEEP,
+            limiter_state: Self::FRAMEPACE_LIMITER_STATE,
+            frame_repeats: Self::FRAMEPACE_FRAME_REPEATS,
+            frame_skips: Self::FRAMEPACE_FRAME_SKIPS,
+            work_time: Self::FRAMEPACE_WORK_TIME,
+            frametime_units: FrametimeUnits::Millis,
+            history_length: Self::DEFAULT_HISTORY_LENGTH,
+        }
+    }
+}
 
 impl Plugin for DiagnosticsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, Self::diagnostic_system);
+        if app.world().contains_resource::<DiagnosticsPlugin>() {
+            bevy_log::warn!("`DiagnosticsPlugin` was added more than once, ignoring");
+            return;
+        }
+
+        app.insert_resource(self.clone())
+            .add_systems(Update, Self::diagnostic_system);
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, Self::cadence_system);
 
-        app.register_diagnostic(Diagnostic::new(Self::FRAMEPACE_FRAMETIME).with_suffix("ms"));
-        app.register_diagnostic(Diagnostic::new(Self::FRAMEPACE_OVERSLEEP).with_suffix("µs"));
+        let frametime_suffix = match self.frametime_units {
+            FrametimeUnits::Millis => "ms",
+            FrametimeUnits::Fps => "fps",
+        };
+        let smoothing = 2.0 / (self.history_length.max(1) as f64 + 1.0);
+        let smoothed = |path: &DiagnosticPath| {
+            Diagnostic::new(path.clone())
+                .with_max_history_length(self.history_length.max(1))
+                .with_smoothing_factor(smoothing)
+        };
+        app.register_diagnostic(smoothed(&self.frametime).with_suffix(frametime_suffix));
+        app.register_diagnostic(smoothed(&self.oversleep).with_suffix("µs"));
+        app.register_diagnostic(Diagnostic::new(self.limiter_state.clone()));
+        app.register_diagnostic(Diagnostic::new(self.frame_repeats.clone()));
+        app.register_diagnostic(Diagnostic::new(self.frame_skips.clone()));
+        app.register_diagnostic(smoothed(&self.work_time).with_suffix("ms"));
+    }
+
+    fn is_unique(&self) -> bool {
+        false
     }
 }
 
 impl DiagnosticsPlugin {
+    /// The default [`DiagnosticsPlugin::history_length`], one second at 120 fps.
+    pub const DEFAULT_HISTORY_LENGTH: usize = 120;
+
     /// [`DiagnosticPath`] for the frametime
     pub const FRAMEPACE_FRAMETIME: DiagnosticPath =
         DiagnosticPath::const_new("framepace/frametime");
-    /// [`DiagnosticPath`] for failures to meet frame time target
+    /// [`DiagnosticPath`] for failures to meet frame time target, positive when the frame was too
+    /// long, negative when it was too short
     pub const FRAMEPACE_OVERSLEEP: DiagnosticPath =
         DiagnosticPath::const_new("framepace/oversleep");
+    /// [`DiagnosticPath`] for the applied limiter, encoded by [`DiagnosticsPlugin::limiter_state`]
+    pub const FRAMEPACE_LIMITER_STATE: DiagnosticPath =
+        DiagnosticPath::const_new("framepace/limiter_state");
+    /// [`DiagnosticPath`] for the number of display refreshes per frame beyond the paced cadence,
+    /// where the previous frame was scanned out again. Native only, as it needs the detected
+    /// refresh rate.
+    pub const FRAMEPACE_FRAME_REPEATS: DiagnosticPath =
+        DiagnosticPath::const_new("framepace/frame_repeats");
+    /// [`DiagnosticPath`] for the number of display refreshes per frame short of the paced cadence,
+    /// where a frame was replaced before it could be scanned out for its full duration. Native
+    /// only, as it needs the detected refresh rate.
+    pub const FRAMEPACE_FRAME_SKIPS: DiagnosticPath =
+        DiagnosticPath::const_new("framepace/frame_skips");
+    /// [`DiagnosticPath`] for the time spent working each frame, excluding the limiter sleep, see
+    /// [`crate::FramePaceStats::work_time`]
+    pub const FRAMEPACE_WORK_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("framepace/work_time");
+
+    /// Records diagnostics under `prefix` instead of `framepace`, e.g. `"{prefix}/frametime"`.
+    pub fn with_prefix(prefix: &str) -> Self {
+        DiagnosticsPlugin {
+            frametime: DiagnosticPath::new(format!("{prefix}/frametime")),
+            oversleep: DiagnosticPath::new(format!("{prefix}/oversleep")),
+            limiter_state: DiagnosticPath::new(format!("{prefix}/limiter_state")),
+            frame_repeats: DiagnosticPath::new(format!("{prefix}/frame_repeats")),
+            frame_skips: DiagnosticPath::new(format!("{prefix}/frame_skips")),
+            work_time: DiagnosticPath::new(format!("{prefix}/work_time")),
+            frametime_units: FrametimeUnits::Millis,
+            history_length: Self::DEFAULT_HISTORY_LENGTH,
+        }
+    }
+
+    /// Records the frametime in the provided `units`.
+    pub fn with_frametime_units(mut self, units: FrametimeUnits) -> Self {
+        self.frametime_units = units;
+        self
+    }
+
+    /// Encodes the [`Limiter`] variant as a diagnostic value:
+    ///
+    /// | value | limiter                   |
+    /// | ----- | ------------------------- |
+    /// | 0     | [`Limiter::Off`]          |
+    /// | 1     | [`Limiter::Auto`]         |
+    /// | 2     | [`Limiter::Manual`]       |
+    /// | 3     | [`Limiter::AutoCapped`]   |
+    /// | 4     | [`Limiter::Adaptive`]     |
+    /// | 5     | [`Limiter::AudioLocked`]  |
+    /// | 6     | [`Limiter::IdleRamp`]     |
+    /// | 7     | [`Limiter::Consistent`]   |
+    /// | 8     | [`Limiter::Custom`]       |
+    pub fn limiter_state(limiter: &Limiter) -> f64 {
+        match limiter {
+            Limiter::Off => 0.0,
+            Limiter::Auto => 1.0,
+            Limiter::Manual(_) => 2.0,
+            Limiter::AutoCapped(_) => 3.0,
+            Limiter::Adaptive(_) => 4.0,
+            Limiter::AudioLocked { .. } => 5.0,
+            Limiter::IdleRamp(_) => 6.0,
+            Limiter::Consistent(_) => 7.0,
+            Limiter::Custom => 8.0,
+        }
+    }
+
+    /// Estimates the display refreshes repeated and skipped in a frame lasting `interval`, relative
+    /// to the paced cadence of `target`, on a display refreshing every `refresh`.
+    ///
+    /// Without vblank timestamps, the refreshes spanned by a frame are estimated as `interval /
+    /// refresh`, rounded to the nearest refresh.
+    pub fn cadence_error(interval: Duration, target: Duration, refresh: Duration) -> (u32, u32) {
+        let refreshes =
+            |duration: Duration| (duration.as_secs_f64() / refresh.as_secs_f64()).round() as u32;
+        let expected = refreshes(target).max(1);
+        let actual = refreshes(interval);
+        (
+            actual.saturating_sub(expected),
+            expected.saturating_sub(actual),
+        )
+    }
 
     /// Updates diagnostic data from measurements
     pub fn diagnostic_system(
         mut diagnostics: Diagnostics,
         time: Res<Time>,
         stats: Res<crate::FramePaceStats>,
+        limiter: Res<crate::LimiterHandle>,
+        paths: Res<DiagnosticsPlugin>,
     ) {
         if time.delta_secs_f64() == 0.0 {
             return;
         }
 
-        let frametime_millis = stats.frametime.try_lock().unwrap().as_secs_f64() * 1_000_f64;
-        let error_micros = stats.oversleep.try_lock().unwrap().as_secs_f64() * 1_000_000_f64;
+        let frametime = match paths.frametime_units {
+            FrametimeUnits::Millis => stats.frametime().as_secs_f64() * 1_000_f64,
+            FrametimeUnits::Fps => 1.0 / (stats.frametime() + stats.sleep_time()).as_secs_f64(),
+        };
+        if frametime.is_finite() {
+            diagnostics.add_measurement(&paths.frametime, || frametime);
+        }
+        let error_micros = stats.sleep_error_secs() * 1_000_000_f64;
+
+        diagnostics.add_measurement(&paths.oversleep, || error_micros);
+        diagnostics.add_measurement(&paths.work_time, || {
+            stats.work_time().as_secs_f64() * 1_000_f64
+        });
+        if let Some(limiter) = limiter.get() {
+            diagnostics.add_measurement(&paths.limiter_state, || Self::limiter_state(&limiter));
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cadence_system(
+        mut diagnostics: Diagnostics,
+        time: Res<Time<Real>>,
+        stats: Res<crate::FramePaceStats>,
+        detected: Option<Res<crate::DetectedFrametime>>,
+        paths: Res<DiagnosticsPlugin>,
+    ) {
+        let (Some(refresh), Some(target)) = (
+            detected.and_then(|detected| detected.0),
+            stats.target_frametime(),
+        ) else {
+            return;
+        };
+        let (repeats, skips) = Self::cadence_error(time.delta(), target, refresh);
+        diagnostics.add_measurement(&paths.frame_repeats, || repeats as f64);
+        diagnostics.add_measurement(&paths.frame_skips, || skips as f64);
+    }
+}
+
+/// Periodically logs a structured summary of the pacing measurements, for production telemetry.
+///
+/// Every [`TelemetryPlugin::interval`], the measurements since the previous log are aggregated and
+/// logged at the `info` level, with named `tracing` fields instead of a formatted message, so they
+/// can be parsed by log pipelines.
+#[derive(Debug, Clone, Resource)]
+pub struct TelemetryPlugin {
+    /// How often the summary is logged.
+    pub interval: Duration,
+}
+
+impl Default for TelemetryPlugin {
+    fn default() -> Self {
+        TelemetryPlugin {
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.clone())
+            .add_systems(Update, Self::telemetry_system);
+    }
+}
+
+/// Measurements accumulated since the last telemetry log.
+#[derive(Default)]
+struct TelemetrySamples {
+    elapsed: Duration,
+    frametimes: Vec<Duration>,
+    oversleep: Duration,
+}
+
+impl TelemetryPlugin {
+    fn telemetry_system(
+        time: Res<Time<Real>>,
+        config: Res<TelemetryPlugin>,
+        stats: Res<crate::FramePaceStats>,
+        #[cfg(not(target_arch = "wasm32"))] detected: Option<Res<crate::DetectedFrametime>>,
+        mut samples: Local<TelemetrySamples>,
+    ) {
+        samples.elapsed += time.delta();
+        samples.frametimes.push(stats.frametime());
+        samples.oversleep += stats.oversleep();
+        if samples.elapsed < config.interval {
+            return;
+        }
+
+        let samples = std::mem::take(&mut *samples);
+        let mut frametimes = samples.frametimes;
+        frametimes.sort_unstable();
+        let frames = frametimes.len() as u32;
+        let mean = frametimes.iter().sum::<Duration>() / frames;
+        let p99 = frametimes[((frames - 1) as f64 * 0.99).round() as usize];
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let detected_refresh_hz = detected
+            .and_then(|detected| detected.0)
+            .map_or(0.0, |frametime| 1.0 / frametime.as_secs_f64());
+        #[cfg(target_arch = "wasm32")]
+        let detected_refresh_hz = 0.0;
 
-        diagnostics.add_measurement(&Self::FRAMEPACE_FRAMETIME, || frametime_millis);
-        diagnostics.add_measurement(&Self::FRAMEPACE_OVERSLEEP, || error_micros);
+        bevy_log::info!(
+            target_ms = stats
+                .target_frametime()
+                .map_or(0.0, |target| target.as_secs_f64() * 1_000_f64),
+            mean_frametime_ms = mean.as_secs_f64() * 1_000_f64,
+            p99_frametime_ms = p99.as_secs_f64() * 1_000_f64,
+            mean_oversleep_us = (samples.oversleep / frames).as_secs_f64() * 1_000_000_f64,
+            detected_refresh_hz,
+            frames,
+            "framepace telemetry"
+        );
     }
 }
@@ -28,30 +28,65 @@
 #![deny(missing_docs)]
 
 use bevy_app::prelude::*;
+use bevy_core::FrameCount;
 use bevy_ecs::prelude::*;
 use bevy_reflect::prelude::*;
+#[cfg(feature = "render")]
 use bevy_render::{Render, RenderApp, RenderSet};
 use bevy_utils::Instant;
 
-#[cfg(not(target_arch = "wasm32"))]
-use bevy_window::prelude::*;
-#[cfg(not(target_arch = "wasm32"))]
+use bevy_window::{prelude::*, WindowOccluded};
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
 use bevy_winit::WinitWindows;
 
 use std::{
-    sync::{Arc, Mutex},
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::Duration,
 };
 
+/// Implements [`Debug`](std::fmt::Debug) for a wrapper around a closure or trait object, which
+/// can't be printed.
+macro_rules! opaque_debug {
+    ($ty:ident) => {
+        impl std::fmt::Debug for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_tuple(stringify!($ty)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "test_assertions")]
+pub mod assertions;
+pub mod conditions;
 #[cfg(feature = "framepace_debug")]
 pub mod debug;
+#[cfg(feature = "asset")]
+pub mod loading;
+#[cfg(feature = "power")]
+pub mod power;
 
 /// Adds framepacing and framelimiting functionality to your [`App`].
+///
+/// With the `render` feature, the default, the limiter runs at the end of the render app's
+/// schedule. Without it, the limiter runs in the [`Last`] schedule of the main app. Display refresh
+/// rate detection requires the `winit` feature, also enabled by default; without it, limiters
+/// following the display rate fall back to their behavior when the refresh rate is unknown.
 #[derive(Debug, Clone, Component)]
 pub struct FramepacePlugin;
 impl Plugin for FramepacePlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<FramepaceSettings>();
+        app.register_type::<FramepaceSettings>()
+            .register_type::<FramepaceActivity>()
+            .register_type::<FramepaceSpeedScale>()
+            .register_type::<FramepaceAccuracyPriority>()
+            .register_type::<EffectiveState>()
+            .register_type::<FramePaceSnapshot>()
+            .add_event::<FramepaceSettingsChanged>();
 
         let limit = FrametimeLimit::default();
         let settings = FramepaceSettings::default();
@@ -59,34 +94,301 @@ impl Plugin for FramepacePlugin {
         let stats = FramePaceStats::default();
 
         app.insert_resource(settings)
+            .init_resource::<FramepaceActivity>()
+            .init_resource::<FramepaceSpeedScale>()
+            .init_resource::<FramepaceAccuracyPriority>()
+            .init_resource::<EffectiveState>()
             .insert_resource(settings_proxy.clone())
+            .insert_resource(LimiterHandle {
+                applied: settings_proxy.limiter.clone(),
+                requested: Default::default(),
+            })
             .insert_resource(limit.clone())
             .insert_resource(stats.clone())
-            .add_systems(Update, update_proxy_resources);
+            .init_resource::<WindowOcclusion>()
+            .init_resource::<LastInput>()
+            .init_resource::<FramepaceStatsConfig>()
+            .init_resource::<FramePaceSnapshot>()
+            .add_event::<FramepaceStalled>()
+            .add_event::<FramepaceHeadroom>()
+            .add_event::<FrameTimingEvent>()
+            .add_observer(set_limiter)
+            .add_systems(
+                Update,
+                (
+                    track_occlusion.before(update_proxy_resources),
+                    apply_limiter_requests.before(update_proxy_resources),
+                    track_input,
+                    update_proxy_resources,
+                    update_proxy_slot::<FramepaceSleepHook>,
+                    update_proxy_slot::<FramepaceBudgetHook>,
+                    update_proxy_slot::<FramepacePacing>,
+                    update_proxy_slot::<FramepacePhase>,
+                    send_stall_events,
+                    send_headroom_events,
+                    exit_after_frames,
+                    send_frame_timing_events,
+                    update_stats_label,
+                    update_stats_snapshot,
+                ),
+            );
 
         #[cfg(not(target_arch = "wasm32"))]
-        app.add_systems(Update, get_display_refresh_rate);
+        let wakeup = FramepaceWakeup::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        let present_feedback = FramepacePresentFeedback::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        limiter_app(app)
+            .insert_resource(wakeup.clone())
+            .insert_resource(present_feedback.clone());
+        #[cfg(not(target_arch = "wasm32"))]
+        app.insert_resource(wakeup)
+            .insert_resource(present_feedback)
+            .init_resource::<DetectedFrametime>()
+            .add_event::<RefreshRateChanged>()
+            .add_systems(
+                Update,
+                (
+                    update_proxy_slot::<FramepaceTimeline>,
+                    get_display_refresh_rate,
+                    probe_sleep_accuracy,
+                    calibrate_sleeper,
+                ),
+            );
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Update, warn_unsupported_limiter);
+        #[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+        app.add_systems(
+            Update,
+            (
+                detect_followed_refresh_rate
+                    .run_if(detects_refresh_rate)
+                    .before(get_display_refresh_rate),
+                warn_reactive_update_mode,
+            ),
+        );
 
-        app.sub_app_mut(RenderApp)
+        limiter_app(app)
             .insert_resource(FrameTimer::default())
+            .init_resource::<AdaptiveState>()
+            .init_resource::<ConsistentState>()
             .insert_resource(settings_proxy)
             .insert_resource(limit)
-            .insert_resource(stats)
-            .add_systems(
-                Render,
+            .insert_resource(stats);
+
+        #[cfg(feature = "render")]
+        app.sub_app_mut(RenderApp).add_systems(
+            Render,
+            (
+                timestamp_present
+                    .in_set(RenderSet::Render)
+                    .after(bevy_render::renderer::render_system),
                 framerate_limiter
                     .in_set(RenderSet::Cleanup)
                     .after(World::clear_entities),
-            );
+            ),
+        );
+        #[cfg(not(feature = "render"))]
+        app.add_systems(Last, framerate_limiter);
+    }
+}
+
+/// The app the frame limiter runs in, the render app if the `render` feature is enabled.
+fn limiter_app(app: &mut App) -> &mut SubApp {
+    #[cfg(feature = "render")]
+    {
+        app.sub_app_mut(RenderApp)
+    }
+    #[cfg(not(feature = "render"))]
+    {
+        app.main_mut()
+    }
+}
+
+/// Describes what this build of `bevy_framepace` supports, see [`capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version of the crate, e.g. `"0.18.0"`.
+    pub version: &'static str,
+    /// Whether the limiter can sleep. This is not possible on `wasm32`, where the browser paces
+    /// frames instead.
+    pub sleep: bool,
+    /// Whether the display refresh rate can be detected for [`Limiter::Auto`]. This requires the
+    /// `winit` feature, and is not possible on `wasm32`.
+    pub refresh_rate_detection: bool,
+    /// Whether the `framepace_debug` feature, providing the `debug` module, is enabled.
+    pub debug: bool,
+    /// Whether the `test_assertions` feature, providing the `assertions` module, is enabled.
+    pub test_assertions: bool,
+    /// Whether the `profiling` feature, see [`profiling_requested`], is enabled.
+    pub profiling: bool,
+    /// The names of the supported [`Limiter`] variants.
+    pub limiters: &'static [&'static str],
+}
+
+/// Returns the features compiled into this build of `bevy_framepace`, so tools can adapt their
+/// settings to the linked crate.
+pub const fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        sleep: cfg!(not(target_arch = "wasm32")),
+        refresh_rate_detection: cfg!(all(not(target_arch = "wasm32"), feature = "winit")),
+        debug: cfg!(feature = "framepace_debug"),
+        test_assertions: cfg!(feature = "test_assertions"),
+        profiling: cfg!(feature = "profiling"),
+        limiters: &[
+            "Auto",
+            "AutoCapped",
+            "Manual",
+            "Adaptive",
+            "AudioLocked",
+            "IdleRamp",
+            "Consistent",
+            "Custom",
+            "Off",
+        ],
     }
 }
 
 /// Framepacing plugin configuration.
+///
+/// The default [`FramepaceSettings::limiter`] depends on the platform, see
+/// [`Limiter::platform_default`].
 #[derive(Debug, Clone, Resource, Reflect)]
 #[reflect(Resource)]
 pub struct FramepaceSettings {
+    /// Enables frame limiting. While `false`, frames are not limited, as if the
+    /// [`FramepaceSettings::limiter`] was [`Limiter::Off`], but the configured limiters are kept,
+    /// e.g. to bind an "enable frame limiting" checkbox independently from a framerate slider.
+    /// Defaults to `true`.
+    pub enabled: bool,
     /// Configures the framerate limiting strategy.
     pub limiter: Limiter,
+    /// The framerate limiting strategy used instead of [`FramepaceSettings::limiter`] while
+    /// [`FramepaceActivity`] is `false`, or while all windows are occluded if
+    /// [`FramepaceSettings::pause_when_occluded`] is enabled.
+    pub idle_limiter: Limiter,
+    /// Switches to the [`FramepaceSettings::idle_limiter`] while every window is hidden, minimized,
+    /// or fully occluded by other windows.
+    ///
+    /// Occlusion is a stronger signal than focus: an unfocused window may still be visible and
+    /// animating on screen, while an occluded window can't be seen at all. Occlusion is reported
+    /// by the OS, and not every platform supports it. Defaults to `false`.
+    pub pause_when_occluded: bool,
+    /// The fraction of the target frametime that is always spent sleeping, in the range `0.0..=1.0`.
+    ///
+    /// This reserves idle time every frame, e.g. for background work on other threads. While work
+    /// takes less than `1.0 - reserve_fraction` of the target frametime, this has no effect. When
+    /// work takes longer, the limiter still sleeps for `reserve_fraction` of the target, extending
+    /// the frame beyond the target. With [`Limiter::Auto`], the target is the display's refresh
+    /// frametime, so frames exceeding the work budget will miss the next refresh. Defaults to `0.0`.
+    pub reserve_fraction: f64,
+    /// The shortest target frametime the limiter will ever use, regardless of the [`Limiter`] and the
+    /// display refresh rate.
+    ///
+    /// This is a thermal and power guardrail, e.g. for trivial scenes like menus that would
+    /// otherwise render as fast as a high refresh rate display allows. Unlike
+    /// [`Limiter::AutoCapped`], it applies to every limiter, including [`FramepaceSpeedScale`]
+    /// adjustments. Defaults to [`Duration::ZERO`], which disables the floor.
+    pub min_frametime: Duration,
+    /// The largest fraction of each frame, in the range `0.0..=1.0`, that may be spent working
+    /// rather than sleeping.
+    ///
+    /// When the work of a frame, measured as its frametime relative to the target, exceeds this
+    /// budget, the target frametime is lengthened until the work fits the budget again, lowering
+    /// the framerate to reclaim idle time. This is a power governor for background or always-on
+    /// apps. Defaults to `1.0`, which disables the budget.
+    pub max_cpu_fraction: f64,
+    /// Smooths the oversleep compensation, in the range `0.0..=1.0`.
+    ///
+    /// Each frame, the limiter shortens its sleep by the oversleep of the previous frames, so that
+    /// frames average out to the target frametime. With `1.0`, the default, only the last frame's
+    /// oversleep is compensated, so a single slow frame shortens the sleep of the next frame by the
+    /// whole overshoot, which can cascade into uneven pacing. Lower values compensate an
+    /// exponential moving average of the oversleep instead, weighting the latest frame by this
+    /// factor, absorbing isolated spikes at the cost of correcting persistent drift more slowly.
+    pub oversleep_smoothing: f64,
+    /// Limits the oversleep compensated after a hitch, i.e. a frame that overshot the target by
+    /// more than this duration.
+    ///
+    /// Without a limit, the frame after a large hitch tries to make up the whole overshoot by not
+    /// sleeping at all, producing a noticeably fast catch-up frame. With a limit, at most this much
+    /// of the overshoot is compensated, while smaller jitter is still compensated in full. Defaults
+    /// to `None`. Native only.
+    pub hitch_compensation_limit: Option<Duration>,
+    /// Rounds the framerate of a [`Limiter::Manual`] cap to the nearest integer fps, e.g. 73.4 fps
+    /// to 73 fps, so caps chosen with a slider don't beat against the display. Defaults to `false`.
+    pub snap_to_integer_fps: bool,
+    /// How the detected display refresh rate is rounded before it is used as the target.
+    pub refresh_rounding: RefreshRounding,
+    /// Detects the refresh rate of this monitor, instead of the slowest monitor showing a window.
+    /// See [`available_monitors`] to list the connected monitors. Defaults to `None`. Native only.
+    pub target_monitor: Option<MonitorSelector>,
+    /// The longest the limiter will ever sleep in a single frame, regardless of the target
+    /// frametime, so a misconfigured target can't freeze the app. A warning is logged the first time
+    /// a sleep is clamped. A zero duration disables the clamp, letting the limiter sleep as long as
+    /// the target requires. Defaults to one second.
+    pub max_frame_sleep: Duration,
+    /// How the limiter sleeps until the next frame.
+    pub sleep_strategy: SleepStrategy,
+    /// Measures the sleep accuracy of this machine once, on a background thread, and uses it as
+    /// the spin threshold of [`SleepStrategy::Spin`]. The result is logged and cached in the
+    /// [`SleepCalibration`] resource. Other sleep strategies are unaffected. Defaults to `false`.
+    /// Native only.
+    pub calibrate: bool,
+    /// Allows the limiter sleep to be cut short with [`FramepaceWakeup::wake`].
+    ///
+    /// When enabled, the coarse part of the sleep waits on a condition variable instead of using
+    /// `spin_sleep`'s native sleep. This can be less accurate on some platforms, notably Windows,
+    /// resulting in more spinning. Defaults to `false`. Native only.
+    pub interruptible: bool,
+    /// Where in the render schedule the reported frametime is measured.
+    pub measurement_point: MeasurementPoint,
+    /// Drops the lateness of frames that overshoot the target, instead of catching up, for output at
+    /// a fixed cadence such as streaming.
+    ///
+    /// By default, a late frame shortens the sleep of the next frame by its oversleep, so frames
+    /// average out to the target frametime, at the cost of drifting off the original schedule.
+    /// When enabled, a late frame doesn't sleep, and isn't compensated. Instead, the next frame is
+    /// measured from the last boundary of the schedule, i.e. the previous frame start plus a whole
+    /// number of target frametimes, so it ends on the schedule again. Defaults to `false`.
+    pub drop_late_frames: bool,
+    /// Sends a [`FrameTimingEvent`] every frame. Defaults to `false`.
+    pub frame_timing_events: bool,
+    /// The number of frames kept in the [`FramePaceStats::history`] ring buffer. Set to `0`, the
+    /// default, to disable recording the history.
+    pub history_len: usize,
+    /// The number of frames after startup that are never limited, so the first frames are shown as
+    /// soon as possible. Defaults to `0`.
+    pub fast_startup_frames: u32,
+    /// Keeps measuring [`FramePaceStats`] while frames are not limited, e.g. with [`Limiter::Off`],
+    /// so the uncapped frametime can still be shown. When `false`, the stats keep the values of the
+    /// last limited frame, apart from the [`FramePaceStats::target_frametime`]. Defaults to `true`.
+    pub update_stats_when_off: bool,
+    /// The number of consecutive frames that miss the target frametime before an error is logged
+    /// and a [`FramepaceStalled`] event is sent. Set to `0` to disable stall reporting.
+    pub stall_threshold: u32,
+    /// The [`FramePaceStats::headroom_fraction`] above which a [`FramepaceHeadroom`] event is sent,
+    /// e.g. `0.5` to be notified when frames consistently use less than half of their budget. The
+    /// event is sent again after the headroom dropped below the threshold and recovered. Set to
+    /// `0.0`, the default, to disable the event.
+    pub headroom_threshold: f64,
+    /// Disables the limiter while profiling, see [`profiling_requested`], so captures show the
+    /// uncapped behavior of the app instead of artificially uniform frametimes. Defaults to
+    /// `false`.
+    pub uncap_when_profiling: bool,
+    /// Set by [`FramepaceSettings::step_once`], cleared once forwarded to the limiter.
+    #[reflect(ignore)]
+    step_once: bool,
+    /// Set by [`FramepaceSettings::momentary_uncap`].
+    #[reflect(ignore)]
+    uncapped: bool,
+    /// Set by [`FramepaceSettings::preview_native`].
+    #[reflect(ignore)]
+    preview_native: bool,
+    /// Set while assets are loading, see the `loading` module.
+    #[reflect(ignore)]
+    loading: bool,
 }
 impl FramepaceSettings {
     /// Builds plugin settings with the specified [`Limiter`] configuration.
@@ -94,185 +396,4288 @@ impl FramepaceSettings {
         self.limiter = limiter;
         self
     }
+
+    /// Builds plugin settings that record the last `history_len` frames in
+    /// [`FramePaceStats::history`].
+    pub fn with_history_len(mut self, history_len: usize) -> Self {
+        self.history_len = history_len;
+        self
+    }
+
+    /// Builds plugin settings with the specified idle [`Limiter`], used while [`FramepaceActivity`]
+    /// is `false`.
+    pub fn with_idle_limiter(mut self, idle_limiter: Limiter) -> Self {
+        self.idle_limiter = idle_limiter;
+        self
+    }
+
+    /// Returns the [`Limiter`] to apply given the app's [`FramepaceActivity`].
+    pub fn current_limiter(&self, activity: &FramepaceActivity) -> &Limiter {
+        if activity.0 {
+            &self.limiter
+        } else {
+            &self.idle_limiter
+        }
+    }
+
+    /// The [`Limiter`] applied by the plugin, accounting for activity and occlusion.
+    fn applied_limiter(
+        &self,
+        activity: &FramepaceActivity,
+        occlusion: &WindowOcclusion,
+    ) -> &Limiter {
+        match self.applied_reason(activity, occlusion) {
+            LimiterReason::Disabled
+            | LimiterReason::Uncapped
+            | LimiterReason::Profiling
+            | LimiterReason::Loading => &Limiter::Off,
+            LimiterReason::PreviewNative => &Limiter::Auto,
+            LimiterReason::Occluded | LimiterReason::Inactive => &self.idle_limiter,
+            LimiterReason::Configured => &self.limiter,
+        }
+    }
+
+    /// Why the [`FramepaceSettings::applied_limiter`] was chosen.
+    fn applied_reason(
+        &self,
+        activity: &FramepaceActivity,
+        occlusion: &WindowOcclusion,
+    ) -> LimiterReason {
+        if self.uncapped {
+            LimiterReason::Uncapped
+        } else if self.preview_native {
+            LimiterReason::PreviewNative
+        } else if !self.enabled {
+            LimiterReason::Disabled
+        } else if self.uncap_when_profiling && profiling_requested() {
+            LimiterReason::Profiling
+        } else if self.loading {
+            LimiterReason::Loading
+        } else if self.pause_when_occluded && occlusion.all_occluded {
+            LimiterReason::Occluded
+        } else if !activity.0 {
+            LimiterReason::Inactive
+        } else {
+            LimiterReason::Configured
+        }
+    }
+
+    /// Removes the frame cap while `active` is `true`, e.g. while a "turbo" key is held, and
+    /// restores the configured limiter once it is `false` again.
+    ///
+    /// This is meant to be called every frame with the current state of the condition:
+    ///
+    /// ```ignore
+    /// fn turbo(mut settings: ResMut<FramepaceSettings>, input: Res<ButtonInput<KeyCode>>) {
+    ///     FramepaceSettings::momentary_uncap(&mut settings, input.pressed(KeyCode::Tab));
+    /// }
+    /// ```
+    ///
+    /// It takes the `ResMut` rather than `&mut self`, so the settings are only marked as changed
+    /// when the state actually flips, instead of recomputing the applied limiter every frame.
+    pub fn momentary_uncap(settings: &mut impl DetectChangesMut<Inner = Self>, active: bool) {
+        if settings.bypass_change_detection().uncapped != active {
+            settings.bypass_change_detection().uncapped = active;
+            settings.set_changed();
+        }
+    }
+
+    /// Returns `true` while the frame cap is removed by [`FramepaceSettings::momentary_uncap`].
+    pub fn is_uncapped(&self) -> bool {
+        self.uncapped
+    }
+
+    /// Runs at the display refresh rate while `on` is `true`, as if the limiter was
+    /// [`Limiter::Auto`], e.g. for a "preview at full refresh rate" button. The configured
+    /// limiters are restored once it is `false` again.
+    ///
+    /// Unlike [`FramepaceSettings::momentary_uncap`], which takes precedence, frames are still
+    /// paced to the display. This also overrides [`FramepaceSettings::enabled`] and the idle
+    /// limiter. Like [`FramepaceSettings::momentary_uncap`], it takes the `ResMut` so it can be
+    /// called every frame, and only marks the settings as changed when the state flips.
+    pub fn preview_native(settings: &mut impl DetectChangesMut<Inner = Self>, on: bool) {
+        if settings.bypass_change_detection().preview_native != on {
+            settings.bypass_change_detection().preview_native = on;
+            settings.set_changed();
+        }
+    }
+
+    /// Returns `true` while the display refresh rate is previewed by
+    /// [`FramepaceSettings::preview_native`].
+    pub fn is_previewing_native(&self) -> bool {
+        self.preview_native
+    }
+
+    /// Lets the next frame run without sleeping, then re-engages the limiter.
+    ///
+    /// The frame timer is reset after the stepped frame, so it does not distort the frametime or
+    /// oversleep measurements of the frames that follow. Useful for frame-by-frame debugging.
+    pub fn step_once(&mut self) {
+        self.step_once = true;
+    }
 }
 impl Default for FramepaceSettings {
     fn default() -> FramepaceSettings {
         FramepaceSettings {
-            limiter: Limiter::Auto,
+            enabled: true,
+            limiter: Limiter::platform_default(),
+            idle_limiter: Limiter::from_framerate(10.0),
+            pause_when_occluded: false,
+            reserve_fraction: 0.0,
+            min_frametime: Duration::ZERO,
+            max_cpu_fraction: 1.0,
+            max_frame_sleep: Duration::from_secs(1),
+            oversleep_smoothing: 1.0,
+            hitch_compensation_limit: None,
+            snap_to_integer_fps: false,
+            refresh_rounding: RefreshRounding::Down,
+            target_monitor: None,
+            sleep_strategy: SleepStrategy::Spin,
+            calibrate: false,
+            interruptible: false,
+            drop_late_frames: false,
+            measurement_point: MeasurementPoint::Cleanup,
+            frame_timing_events: false,
+            history_len: 0,
+            fast_startup_frames: 0,
+            update_stats_when_off: true,
+            stall_threshold: 30,
+            headroom_threshold: 0.0,
+            uncap_when_profiling: false,
+            step_once: false,
+            uncapped: false,
+            preview_native: false,
+            loading: false,
         }
     }
 }
 
-#[derive(Default, Debug, Clone, Resource)]
-struct FramepaceSettingsProxy {
-    /// Configures the framerate limiting strategy.
-    limiter: Arc<Mutex<Limiter>>,
+/// The environment variable that marks the app as being profiled, see [`profiling_requested`].
+pub const PROFILING_ENV_VAR: &str = "BEVY_FRAMEPACE_PROFILING";
+
+/// Returns `true` if the app is being profiled, either because the `profiling` feature is enabled,
+/// or because the [`PROFILING_ENV_VAR`] environment variable is set. Used by
+/// [`FramepaceSettings::uncap_when_profiling`].
+pub fn profiling_requested() -> bool {
+    static REQUESTED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *REQUESTED.get_or_init(|| {
+        cfg!(feature = "profiling") || std::env::var_os(PROFILING_ENV_VAR).is_some()
+    })
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-impl FramepaceSettingsProxy {
-    fn is_enabled(&self) -> bool {
-        self.limiter.try_lock().iter().any(|l| l.is_enabled())
-    }
+/// Configures how the refresh rate reported by the display is rounded.
+///
+/// Some platforms report a rounded integer refresh rate, e.g. 60 Hz for a 59.94 Hz display. Pacing
+/// slightly faster than the display refreshes makes frames pile up, so the rate is conservatively
+/// rounded down by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum RefreshRounding {
+    /// Subtracts half a hertz from the reported refresh rate, to handle refresh rates that were
+    /// rounded up to the nearest integer.
+    #[default]
+    Down,
+    /// Rounds the reported refresh rate to the nearest integer.
+    Nearest,
+    /// Uses the reported refresh rate as is, in millihertz precision.
+    Exact,
 }
 
-fn update_proxy_resources(settings: Res<FramepaceSettings>, proxy: Res<FramepaceSettingsProxy>) {
-    if settings.is_changed() {
-        if let Ok(mut limiter) = proxy.limiter.try_lock() {
-            *limiter = settings.limiter.clone();
+impl RefreshRounding {
+    /// Returns the framerate to target for a display reporting a refresh rate of `millihertz`.
+    pub fn framerate(self, millihertz: u32) -> f64 {
+        let hertz = millihertz as f64 / 1000.0;
+        match self {
+            RefreshRounding::Down => hertz - 0.5,
+            RefreshRounding::Nearest => hertz.round(),
+            RefreshRounding::Exact => hertz,
         }
     }
 }
 
-/// Configures the framelimiting technique for the app.
-#[derive(Debug, Default, Clone, Reflect)]
-pub enum Limiter {
-    /// Uses the window's refresh rate to set the frametime limit, updating when the window changes
-    /// monitors.
+/// Selects the monitor whose refresh rate is detected, see [`FramepaceSettings::target_monitor`].
+///
+/// Monitor names are provided by the OS, and usually identify a monitor across sessions, as long as
+/// it stays connected to the same port. Indices follow the order the OS lists monitors in, which
+/// can change when monitors are connected or disconnected.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub enum MonitorSelector {
+    /// The primary monitor, as reported by the OS.
+    Primary,
+    /// The monitor at this index in [`available_monitors`].
+    Index(usize),
+    /// The monitor with this [`MonitorInfo::name`].
+    Name(String),
+}
+
+/// A connected monitor, see [`available_monitors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorInfo {
+    /// The index of the monitor, see [`MonitorSelector::Index`].
+    pub index: usize,
+    /// The name of the monitor, if the OS provides one, see [`MonitorSelector::Name`].
+    pub name: Option<String>,
+    /// The current refresh rate of the monitor in millihertz, if known.
+    pub refresh_rate_millihertz: Option<u32>,
+}
+
+/// Configures how the limiter sleeps until the next frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum SleepStrategy {
+    /// Sleeps using the OS for as long as its accuracy allows, and spins for the remainder. This is
+    /// very accurate, at the cost of some CPU use while spinning.
     #[default]
-    Auto,
-    /// Set a fixed manual frametime limit. This should be greater than the monitors frametime
-    /// (`1.0 / monitor frequency`).
-    Manual(Duration),
-    /// Disables frame limiting
-    Off,
+    Spin,
+    /// Uses `spin_sleep`'s native sleep without ever spinning. Accuracy depends entirely on the
+    /// platform's sleep granularity, frames may start late by up to a few milliseconds, but no CPU
+    /// time is spent spinning.
+    NoSpin,
+    /// Sleeps using the OS until the provided spin threshold before the deadline, and spins for
+    /// the remainder. The threshold should be slightly larger than the platform's sleep
+    /// inaccuracy, see [`recommended_spin_threshold`] for a starting point.
+    SpinThreshold(Duration),
 }
 
-impl Limiter {
-    /// Returns `true` if the [`Limiter`] is enabled.
-    pub fn is_enabled(&self) -> bool {
-        !matches!(self, Limiter::Off)
+#[cfg(not(target_arch = "wasm32"))]
+impl SleepStrategy {
+    fn sleep(self, duration: Duration) {
+        match self {
+            SleepStrategy::Spin => spin_sleep::sleep(duration),
+            SleepStrategy::NoSpin => spin_sleep::SpinSleeper::new(0).sleep(duration),
+            SleepStrategy::SpinThreshold(threshold) => {
+                let threshold = threshold.as_nanos().min(u32::MAX as u128) as u32;
+                spin_sleep::SpinSleeper::new(threshold).sleep(duration);
+            }
+        }
     }
 
-    /// Constructs a new [`Limiter`] from the provided `framerate`.
-    pub fn from_framerate(framerate: f64) -> Self {
-        Limiter::Manual(Duration::from_secs_f64(1.0 / framerate))
+    /// Scales the spin threshold of this strategy by `priority`, see [`FramepaceAccuracyPriority`].
+    fn prioritized(self, priority: f32) -> Self {
+        let threshold = match self {
+            SleepStrategy::Spin => {
+                Duration::from_nanos(spin_sleep::SpinSleeper::default().native_accuracy_ns() as u64)
+            }
+            SleepStrategy::NoSpin => return self,
+            SleepStrategy::SpinThreshold(threshold) => threshold,
+        };
+        match priority {
+            priority if priority >= 1.0 => self,
+            priority if priority > 0.0 => SleepStrategy::SpinThreshold(threshold.mul_f32(priority)),
+            _ => SleepStrategy::NoSpin,
+        }
     }
 }
 
-impl std::fmt::Display for Limiter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Limiter::Auto => write!(f, "Auto"),
-            Limiter::Manual(t) => write!(f, "{:.2} fps", 1.0 / t.as_secs_f32()),
-            Limiter::Off => write!(f, "Off"),
-        }
+/// Scales how long the limiter spins at the end of its sleep, from `0.0`, which never spins to save
+/// power, to `1.0`, the default, which spins as configured by [`FramepaceSettings::sleep_strategy`]
+/// for the best accuracy.
+///
+/// This can be changed at any time, e.g. raised during latency critical gameplay and lowered in
+/// menus, without switching between sleep strategies. Native only.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FramepaceAccuracyPriority(pub f32);
+impl Default for FramepaceAccuracyPriority {
+    fn default() -> Self {
+        FramepaceAccuracyPriority(1.0)
     }
 }
 
-/// Current frametime limit based on settings and monitor refresh rate.
-#[derive(Debug, Default, Clone, Resource)]
-struct FrametimeLimit(Arc<Mutex<Duration>>);
+/// A sensible [`SleepStrategy::SpinThreshold`] for the target platform.
+///
+/// Windows has a coarse sleep granularity, even once the timer resolution is raised to 1ms, so the
+/// OS sleep regularly overshoots by close to a millisecond. Linux and macOS sleeps are typically
+/// accurate to within tens to a few hundred microseconds. Other platforms use a conservative value.
+pub fn recommended_spin_threshold() -> Duration {
+    if cfg!(target_os = "windows") {
+        Duration::from_micros(2_000)
+    } else if cfg!(target_os = "linux") {
+        Duration::from_micros(125)
+    } else if cfg!(target_os = "macos") {
+        Duration::from_micros(250)
+    } else {
+        Duration::from_micros(1_000)
+    }
+}
 
-/// Tracks the instant of the end of the previous frame.
-#[derive(Debug, Clone, Resource, Reflect)]
-pub struct FrameTimer {
-    sleep_end: Instant,
+/// Where in the render schedule the frametime reported in [`FramePaceStats`] is measured.
+///
+/// Either way, the frametime is measured from the end of the previous frame's sleep, and the
+/// limiter sleeps at the end of the frame. Bevy presents the swapchain at the end of
+/// [`RenderSet::Render`], the present call blocks if the swapchain has no image available. Neither
+/// point observes when the GPU actually finishes the frame or when it is displayed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum MeasurementPoint {
+    /// Measures at the end of [`RenderSet::Cleanup`], right before the limiter sleeps. This
+    /// includes the present call, as well as the time spent cleaning up the render world.
+    #[default]
+    Cleanup,
+    /// Measures right after the swapchain is presented, at the end of [`RenderSet::Render`]. This
+    /// excludes render world cleanup, reporting the time it took to submit and present the frame.
+    Present,
 }
-impl Default for FrameTimer {
+
+/// Whether something is changing on screen, which switches between the
+/// [`FramepaceSettings::limiter`] and the [`FramepaceSettings::idle_limiter`].
+///
+/// Activity is app-defined: the plugin never changes this resource, the app is responsible for
+/// setting it, e.g. to `false` when none of the entities it cares about have changed recently.
+/// Defaults to `true`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FramepaceActivity(pub bool);
+impl Default for FramepaceActivity {
     fn default() -> Self {
-        FrameTimer {
-            sleep_end: Instant::now(),
+        FramepaceActivity(true)
+    }
+}
+
+/// Multiplies the target frametime of the applied [`Limiter`], without changing the limiter itself.
+///
+/// This allows momentarily raising or lowering the framerate cap programmatically, e.g. raising
+/// it during a slow motion effect for smoother footage with a value of `0.5`. Non-positive values
+/// are ignored. Defaults to `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FramepaceSpeedScale(pub f32);
+impl Default for FramepaceSpeedScale {
+    fn default() -> Self {
+        FramepaceSpeedScale(1.0)
+    }
+}
+
+/// Why the plugin applies the [`EffectiveState::applied`] limiter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum LimiterReason {
+    /// The configured [`FramepaceSettings::limiter`] is applied, including when it is
+    /// [`Limiter::Off`] by the user's choice.
+    Configured,
+    /// Limiting is suspended because [`FramepaceSettings::enabled`] is `false`.
+    Disabled,
+    /// Limiting is suspended by [`FramepaceSettings::momentary_uncap`].
+    Uncapped,
+    /// [`Limiter::Auto`] is applied by [`FramepaceSettings::preview_native`].
+    PreviewNative,
+    /// Limiting is suspended while profiling, see [`FramepaceSettings::uncap_when_profiling`].
+    Profiling,
+    /// Limiting is suspended while assets are loading, see the `loading` module.
+    Loading,
+    /// The [`FramepaceSettings::idle_limiter`] is applied because every window is occluded, see
+    /// [`FramepaceSettings::pause_when_occluded`].
+    Occluded,
+    /// The [`FramepaceSettings::idle_limiter`] is applied because [`FramepaceActivity`] is `false`.
+    Inactive,
+}
+
+/// The limiter the user configured, and the limiter the plugin actually applies, updated during
+/// [`Update`].
+///
+/// This distinguishes a user choosing [`Limiter::Off`] from the plugin temporarily suspending or
+/// replacing the configured limiter, e.g. to show the reason in a settings menu.
+#[derive(Debug, Clone, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct EffectiveState {
+    /// The configured [`FramepaceSettings::limiter`].
+    pub configured: Limiter,
+    /// The limiter applied by the plugin.
+    pub applied: Limiter,
+    /// Why the `applied` limiter differs from the `configured` one, if it does.
+    pub reason: LimiterReason,
+}
+impl Default for EffectiveState {
+    fn default() -> Self {
+        EffectiveState {
+            configured: Limiter::platform_default(),
+            applied: Limiter::platform_default(),
+            reason: LimiterReason::Configured,
         }
     }
 }
+impl EffectiveState {
+    /// Returns `true` if the plugin limits the framerate, i.e. the applied limiter is enabled.
+    pub fn is_limiting(&self) -> bool {
+        self.applied.is_enabled()
+    }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn get_display_refresh_rate(
-    settings: Res<FramepaceSettings>,
-    winit: NonSend<WinitWindows>,
-    windows: Query<Entity, With<Window>>,
-    frame_limit: Res<FrametimeLimit>,
+    /// Returns `true` if the configured limiter is suspended or replaced by the plugin.
+    pub fn is_suspended(&self) -> bool {
+        self.reason != LimiterReason::Configured
+    }
+}
+
+/// Tracks which windows are occluded, from [`WindowOccluded`] events.
+#[derive(Debug, Default, Resource)]
+struct WindowOcclusion {
+    occluded: bevy_utils::HashSet<Entity>,
+    /// `true` if every window is hidden or occluded.
+    all_occluded: bool,
+}
+
+fn track_occlusion(
+    mut events: EventReader<WindowOccluded>,
+    windows: Query<(Entity, &Window)>,
+    mut occlusion: ResMut<WindowOcclusion>,
 ) {
-    let new_frametime = match settings.limiter {
-        Limiter::Auto => match detect_frametime(winit, windows.iter()) {
-            Some(frametime) => frametime,
-            None => return,
-        },
-        Limiter::Manual(frametime) => frametime,
-        Limiter::Off => {
-            #[cfg(feature = "framepace_debug")]
-            if settings.is_changed() {
-                bevy_log::info!("Frame limiter disabled");
-            }
-            return;
+    for event in events.read() {
+        let occluded = &mut occlusion.bypass_change_detection().occluded;
+        if event.occluded {
+            occluded.insert(event.window);
+        } else {
+            occluded.remove(&event.window);
         }
-    };
+    }
+    let all_occluded = !windows.is_empty()
+        && windows
+            .iter()
+            .all(|(entity, window)| !window.visible || occlusion.occluded.contains(&entity));
+    if occlusion.all_occluded != all_occluded {
+        occlusion.all_occluded = all_occluded;
+    }
+}
 
-    if let Ok(mut limit) = frame_limit.0.try_lock() {
-        if new_frametime != *limit {
-            #[cfg(feature = "framepace_debug")]
-            bevy_log::info!("Frametime limit changed to: {:?}", new_frametime);
-            *limit = new_frametime;
+#[derive(Default, Debug, Clone, Resource)]
+struct FramepaceSettingsProxy {
+    /// Configures the framerate limiting strategy.
+    limiter: Arc<Mutex<Limiter>>,
+    /// The other settings used by the limiter.
+    config: Arc<Mutex<ProxyConfig>>,
+    /// The spin threshold measured by the calibration.
+    spin_calibration: Arc<Mutex<Option<Duration>>>,
+    /// Skip sleeping for the next frame.
+    step_once: Arc<Mutex<bool>>,
+    /// Replaces the computed sleep duration.
+    sleep_hook: Arc<Mutex<Option<FramepaceSleepHook>>>,
+    /// Computes the sleep duration instead of the default pacing.
+    pacing: Arc<Mutex<Option<FramepacePacing>>>,
+    /// Called with the frame budget before sleeping.
+    budget_hook: Arc<Mutex<Option<FramepaceBudgetHook>>>,
+    /// Records the sleep timeline.
+    #[cfg(not(target_arch = "wasm32"))]
+    timeline: Arc<Mutex<Option<FramepaceTimeline>>>,
+    /// Phase reference shared with other apps.
+    shared_phase: Arc<Mutex<Option<FramepacePhase>>>,
+}
+
+/// The [`FramepaceSettings`] used by the limiter, copied into the proxy whenever they change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+struct ProxyConfig {
+    reserve_fraction: f64,
+    sleep_strategy: SleepStrategy,
+    calibrate: bool,
+    accuracy_priority: f32,
+    interruptible: bool,
+    drop_late_frames: bool,
+    measurement_point: MeasurementPoint,
+    history_len: usize,
+    max_cpu_fraction: f64,
+    max_frame_sleep: Duration,
+    oversleep_smoothing: f64,
+    hitch_compensation_limit: Option<Duration>,
+    stall_threshold: u32,
+    headroom_threshold: f64,
+    fast_startup_frames: u32,
+    update_stats_when_off: bool,
+}
+
+impl ProxyConfig {
+    fn new(settings: &FramepaceSettings, accuracy_priority: &FramepaceAccuracyPriority) -> Self {
+        ProxyConfig {
+            reserve_fraction: settings.reserve_fraction.clamp(0.0, 1.0),
+            sleep_strategy: settings.sleep_strategy,
+            calibrate: settings.calibrate,
+            accuracy_priority: accuracy_priority.0,
+            interruptible: settings.interruptible,
+            drop_late_frames: settings.drop_late_frames,
+            measurement_point: settings.measurement_point,
+            history_len: settings.history_len,
+            max_cpu_fraction: settings.max_cpu_fraction.clamp(0.0, 1.0),
+            max_frame_sleep: settings.max_frame_sleep,
+            oversleep_smoothing: settings.oversleep_smoothing.clamp(0.0, 1.0),
+            hitch_compensation_limit: settings.hitch_compensation_limit,
+            stall_threshold: settings.stall_threshold,
+            headroom_threshold: settings.headroom_threshold,
+            fast_startup_frames: settings.fast_startup_frames,
+            update_stats_when_off: settings.update_stats_when_off,
         }
     }
+
+    /// The sleep strategy, using the calibrated spin threshold if there is one, and scaled by the
+    /// [`FramepaceAccuracyPriority`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sleep_strategy(&self, spin_calibration: Option<Duration>) -> SleepStrategy {
+        let strategy = match spin_calibration {
+            Some(threshold) if self.calibrate && self.sleep_strategy == SleepStrategy::Spin => {
+                SleepStrategy::SpinThreshold(threshold)
+            }
+            _ => self.sleep_strategy,
+        };
+        strategy.prioritized(self.accuracy_priority)
+    }
+
+    /// The work budget as a fraction of the frame, or `None` if there is no budget.
+    fn max_cpu_fraction(&self) -> Option<f64> {
+        Some(self.max_cpu_fraction).filter(|fraction| *fraction > 0.0 && *fraction < 1.0)
+    }
+
+    /// The weight of the latest oversleep in its moving average, or `None` if it isn't smoothed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn oversleep_smoothing(&self) -> Option<f64> {
+        Some(self.oversleep_smoothing).filter(|smoothing| *smoothing > 0.0 && *smoothing < 1.0)
+    }
+
+    /// The longest sleep of a single frame, or `None` if unset.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn max_frame_sleep(&self) -> Option<Duration> {
+        Some(self.max_frame_sleep).filter(|max| !max.is_zero())
+    }
+
+    /// The headroom threshold of the [`FramepaceHeadroom`] event, or `None` if it is disabled.
+    fn headroom_threshold(&self) -> Option<f64> {
+        Some(self.headroom_threshold).filter(|threshold| *threshold > 0.0)
+    }
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn detect_frametime(
-    winit: NonSend<WinitWindows>,
-    windows: impl Iterator<Item = Entity>,
-) -> Option<Duration> {
-    let best_framerate = {
-        windows
-            .filter_map(|e| winit.get_window(e))
-            .filter_map(|w| w.current_monitor())
-            .filter_map(|monitor| monitor.refresh_rate_millihertz())
-            .min()? as f64
-            / 1000.0
-            - 0.5 // Winit only provides integer refresh rate values. We need to round down to handle the worst case scenario of a rounded refresh rate.
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        ProxyConfig::new(
+            &FramepaceSettings::default(),
+            &FramepaceAccuracyPriority::default(),
+        )
+    }
+}
+
+/// A cheaply clonable handle to the currently applied [`Limiter`], that can be read outside of the
+/// ECS, e.g. from FFI callbacks that don't have access to the bevy `World`.
+///
+/// Clone this resource once, and store the clone wherever it is needed. The handle reflects the
+/// limiter applied by the plugin, which is updated from [`FramepaceSettings`] during [`Update`].
+/// The limiter can also be changed from any thread with [`LimiterHandle::set`].
+#[derive(Debug, Clone, Resource)]
+pub struct LimiterHandle {
+    applied: Arc<Mutex<Limiter>>,
+    /// A limiter set from outside of the ECS, waiting to be applied to the settings.
+    requested: Arc<Mutex<Option<Limiter>>>,
+}
+impl LimiterHandle {
+    /// Returns the currently applied [`Limiter`], or `None` if it is being updated.
+    pub fn get(&self) -> Option<Limiter> {
+        self.applied.try_lock().ok().map(|limiter| limiter.clone())
+    }
+
+    /// Requests [`FramepaceSettings::limiter`] to be replaced with `limiter`. This can be called
+    /// from any thread, the request is applied during the next [`Update`]. If several requests are
+    /// made within a frame, the last one wins.
+    pub fn set(&self, limiter: Limiter) {
+        let mut requested = self
+            .requested
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *requested = Some(limiter);
+    }
+}
+
+fn apply_limiter_requests(handle: Res<LimiterHandle>, mut settings: ResMut<FramepaceSettings>) {
+    let Ok(mut requested) = handle.requested.try_lock() else {
+        return;
     };
+    if let Some(limiter) = requested.take() {
+        settings.limiter = limiter;
+    }
+}
+
+impl FramepaceSettingsProxy {
+    fn is_enabled(&self) -> bool {
+        self.limiter.try_lock().iter().any(|l| l.is_enabled())
+    }
+
+    /// Reads the settings used by the limiter, once per frame.
+    fn config(&self) -> ProxyConfig {
+        *self
+            .config
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn set_config(&self, config: ProxyConfig) {
+        *self
+            .config
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = config;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spin_calibration(&self) -> Option<Duration> {
+        self.spin_calibration
+            .try_lock()
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sleep_hook(&self) -> Option<FramepaceSleepHook> {
+        self.sleep_hook
+            .try_lock()
+            .ok()
+            .and_then(|hook| hook.clone())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn timeline(&self) -> Option<FramepaceTimeline> {
+        self.timeline
+            .try_lock()
+            .ok()
+            .and_then(|timeline| timeline.clone())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pacing(&self) -> Option<FramepacePacing> {
+        self.pacing
+            .try_lock()
+            .ok()
+            .and_then(|pacing| pacing.clone())
+    }
+
+    fn budget_hook(&self) -> Option<FramepaceBudgetHook> {
+        self.budget_hook
+            .try_lock()
+            .ok()
+            .and_then(|hook| hook.clone())
+    }
+
+    fn adaptive(&self) -> Option<AdaptiveSettings> {
+        match self.limiter.try_lock().as_deref() {
+            Ok(Limiter::Adaptive(adaptive)) => Some(adaptive.clone()),
+            _ => None,
+        }
+    }
+
+    fn consistent(&self) -> Option<ConsistentSettings> {
+        match self.limiter.try_lock().as_deref() {
+            Ok(Limiter::Consistent(consistent)) => Some(consistent.clone()),
+            _ => None,
+        }
+    }
+
+    /// The reference instant frames should be aligned to, if any.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn phase(&self) -> Option<Instant> {
+        match self.limiter.try_lock().as_deref() {
+            Ok(Limiter::AudioLocked { phase, .. }) => *phase,
+            _ => self.shared_phase().and_then(|phase| phase.get()),
+        }
+    }
 
-    let best_frametime = Duration::from_secs_f64(1.0 / best_framerate);
-    Some(best_frametime)
+    #[cfg(not(target_arch = "wasm32"))]
+    fn shared_phase(&self) -> Option<FramepacePhase> {
+        self.shared_phase
+            .try_lock()
+            .ok()
+            .and_then(|phase| phase.clone())
+    }
+
+    /// Returns `true` once if a single uncapped frame was requested.
+    fn take_step_once(&self) -> bool {
+        self.step_once
+            .try_lock()
+            .map(|mut step| std::mem::take(&mut *step))
+            .unwrap_or_default()
+    }
 }
 
-/// Holds frame time measurements for framepacing diagnostics
-#[derive(Clone, Debug, Default, Resource)]
-pub struct FramePaceStats {
-    frametime: Arc<Mutex<Duration>>,
-    oversleep: Arc<Mutex<Duration>>,
+/// An optional resource of the main world mirrored into its slot of the
+/// [`FramepaceSettingsProxy`], so the limiter can use it.
+trait ProxySlot: Resource + Clone {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>>;
 }
 
-/// Accurately sleeps until it's time to start the next frame.
-///
-/// The `spin_sleep` dependency makes it possible to get extremely accurate sleep times across
-/// platforms. Using `std::thread::sleep()` will not be precise enough, especially windows. Using a
-/// spin lock, even with `std::hint::spin_loop()`, will result in significant power usage.
-///
-/// `spin_sleep` sleeps as long as possible given the platform's sleep accuracy, and spins for the
-/// remainder. The dependency is however not WASM compatible, which is fine, because frame limiting
-/// should not be used in a browser; this would compete with the browser's frame limiter.
-#[allow(unused_variables)]
-fn framerate_limiter(
-    mut timer: ResMut<FrameTimer>,
-    target_frametime: Res<FrametimeLimit>,
-    stats: Res<FramePaceStats>,
-    settings: Res<FramepaceSettingsProxy>,
-) {
-    if let Ok(limit) = target_frametime.0.try_lock() {
-        let frame_time = timer.sleep_end.elapsed();
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let oversleep = stats
-                .oversleep
-                .try_lock()
-                .as_deref()
-                .cloned()
-                .unwrap_or_default();
-            let sleep_time = limit.saturating_sub(frame_time + oversleep);
-            if settings.is_enabled() {
-                spin_sleep::sleep(sleep_time);
-            }
+/// Copies the resource into the proxy when it changes, and clears the slot once it is removed.
+fn update_proxy_slot<R: ProxySlot>(resource: Option<Res<R>>, proxy: Res<FramepaceSettingsProxy>) {
+    if let Ok(mut slot) = R::slot(&proxy).try_lock() {
+        match resource {
+            Some(resource) if resource.is_changed() => *slot = Some(resource.clone()),
+            Some(_) => (),
+            None => *slot = None,
         }
+    }
+}
+
+/// Sent whenever the applied [`Limiter`] changes to a new value, because the
+/// [`FramepaceSettings`] resource or the [`FramepaceActivity`] changed.
+///
+/// This lets other plugins react to framepace configuration changes without duplicating change
+/// detection on the settings resource.
+#[derive(Debug, Clone, Event)]
+pub struct FramepaceSettingsChanged {
+    /// The newly applied framerate limiting strategy.
+    pub limiter: Limiter,
+}
 
-        let frame_time_total = timer.sleep_end.elapsed();
-        timer.sleep_end = Instant::now();
-        if let Ok(mut frametime) = stats.frametime.try_lock() {
-            *frametime = frame_time;
+#[allow(clippy::too_many_arguments)]
+fn update_proxy_resources(
+    mut settings: ResMut<FramepaceSettings>,
+    activity: Res<FramepaceActivity>,
+    occlusion: Res<WindowOcclusion>,
+    accuracy_priority: Res<FramepaceAccuracyPriority>,
+    proxy: Res<FramepaceSettingsProxy>,
+    mut changed: EventWriter<FramepaceSettingsChanged>,
+    mut effective: ResMut<EffectiveState>,
+    mut logged_profiling: Local<bool>,
+) {
+    if settings.step_once {
+        if let Ok(mut step) = proxy.step_once.try_lock() {
+            *step = true;
+            settings.bypass_change_detection().step_once = false;
         }
-        if let Ok(mut oversleep) = stats.oversleep.try_lock() {
-            *oversleep = frame_time_total.saturating_sub(*limit);
+    }
+    if settings.is_changed() || accuracy_priority.is_changed() {
+        proxy.set_config(ProxyConfig::new(&settings, &accuracy_priority));
+    }
+    if settings.is_changed() || activity.is_changed() || occlusion.is_changed() {
+        if settings.uncap_when_profiling && profiling_requested() && !*logged_profiling {
+            bevy_log::info!("Profiling detected, frame limiting is disabled");
+            *logged_profiling = true;
         }
+        let current = settings.applied_limiter(&activity, &occlusion);
+        effective.set_if_neq(EffectiveState {
+            configured: settings.limiter.clone(),
+            applied: current.clone(),
+            reason: settings.applied_reason(&activity, &occlusion),
+        });
+        if let Ok(mut limiter) = proxy.limiter.try_lock() {
+            if *limiter != *current {
+                changed.send(FramepaceSettingsChanged {
+                    limiter: current.clone(),
+                });
+            }
+            *limiter = current.clone();
+        }
+    }
+}
+
+/// Replaces [`FramepaceSettings::limiter`] when triggered, e.g. with
+/// `commands.trigger(SetLimiter(Limiter::FPS_30))`.
+///
+/// This controls the limiter without access to the [`FramepaceSettings`] resource.
+#[derive(Debug, Clone, Event)]
+pub struct SetLimiter(pub Limiter);
+
+fn set_limiter(trigger: Trigger<SetLimiter>, mut settings: ResMut<FramepaceSettings>) {
+    settings.limiter = trigger.event().0.clone();
+}
+
+/// Sent once when the number of consecutive frames missing the target frametime reaches
+/// [`FramepaceSettings::stall_threshold`].
+#[derive(Debug, Clone, Event)]
+pub struct FramepaceStalled {
+    /// The number of consecutive frames that missed the target frametime.
+    pub missed_frames: u32,
+}
+
+/// Sent once when the [`FramePaceStats::headroom_fraction`] rises above
+/// [`FramepaceSettings::headroom_threshold`], i.e. when frames consistently finish well within their
+/// budget. Adaptive systems or the user can use this to raise the framerate cap.
+#[derive(Debug, Clone, Event)]
+pub struct FramepaceHeadroom {
+    /// The smoothed headroom fraction when the threshold was crossed.
+    pub headroom_fraction: f64,
+}
+
+/// A hook that replaces the sleep duration computed by the limiter each frame, e.g. to experiment
+/// with other pacing algorithms.
+///
+/// When this resource is present, the hook is called with the computed sleep duration and the
+/// current [`FramePaceStats`], and the limiter sleeps for the returned duration instead. Remove the
+/// resource to go back to the default algorithm. Native only, the limiter never sleeps on the web.
+#[derive(Clone, Resource)]
+pub struct FramepaceSleepHook(pub Arc<SleepHookFn>);
+
+/// The function of a [`FramepaceSleepHook`], called with the computed sleep duration and the
+/// current [`FramePaceStats`].
+pub type SleepHookFn = dyn Fn(Duration, &FramePaceStats) -> Duration + Send + Sync;
+
+impl FramepaceSleepHook {
+    /// Creates a hook from the provided function.
+    pub fn new(
+        hook: impl Fn(Duration, &FramePaceStats) -> Duration + Send + Sync + 'static,
+    ) -> Self {
+        FramepaceSleepHook(Arc::new(hook))
+    }
+}
+
+opaque_debug!(FramepaceSleepHook);
+
+/// Computes the target frametime of [`Limiter::Custom`] from arbitrary app state.
+///
+/// The function is called once per frame during [`Update`], on whichever thread runs the frametime
+/// update system, so it must be `Send + Sync`; share state with it through e.g. atomics or a
+/// mutex. The closure lives in this resource rather than in the [`Limiter`] itself, so the limiter
+/// stays reflectable and comparable. While [`Limiter::Custom`] is applied without this resource,
+/// frames are not limited. Native only.
+#[derive(Clone, Resource)]
+pub struct FramepaceCustomLimiter(pub Arc<CustomLimiterFn>);
+
+/// The function of a [`FramepaceCustomLimiter`], returning the target frametime.
+pub type CustomLimiterFn = dyn Fn() -> Duration + Send + Sync;
+
+impl FramepaceCustomLimiter {
+    /// Creates a custom limiter from the provided function.
+    pub fn new(limiter: impl Fn() -> Duration + Send + Sync + 'static) -> Self {
+        FramepaceCustomLimiter(Arc::new(limiter))
+    }
+}
+
+opaque_debug!(FramepaceCustomLimiter);
+
+/// Assigns limiters to named render targets, e.g. the viewports and panels of an editor, so each
+/// part of the app can request the framerate it needs.
+///
+/// The app presents all of its render targets together, once per frame, so they can't run at
+/// different rates. Instead, while this resource contains active targets, the limiter paces to the
+/// most demanding of them, i.e. the shortest frametime any active target resolves to, see
+/// [`Limiter::resolve`]. Mark a target inactive when it is hidden or idle, e.g. to let a 3D
+/// viewport drive the framerate only while it is visible, and UI panels fall back to a low rate.
+///
+/// The targets replace the target frametime of an enabled [`FramepaceSettings::limiter`], but not
+/// the limiters the plugin applies instead of it, such as the
+/// [`FramepaceSettings::idle_limiter`], see [`LimiterReason`]. Setting the configured limiter to
+/// [`Limiter::Off`] still disables limiting. Targets set to [`Limiter::Off`] or [`Limiter::Custom`]
+/// don't request a frametime. Native only.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepaceTargets {
+    targets: bevy_utils::HashMap<String, (Limiter, bool)>,
+}
+
+impl FramepaceTargets {
+    /// Assigns `limiter` to the target called `name`, adding it as an active target if needed.
+    pub fn insert(&mut self, name: impl Into<String>, limiter: Limiter) {
+        let target = self
+            .targets
+            .entry(name.into())
+            .or_insert((Limiter::Off, true));
+        target.0 = limiter;
+    }
+
+    /// Removes the target called `name`, returning its limiter.
+    pub fn remove(&mut self, name: &str) -> Option<Limiter> {
+        self.targets.remove(name).map(|(limiter, _)| limiter)
+    }
+
+    /// Returns the limiter of the target called `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&Limiter> {
+        self.targets.get(name).map(|(limiter, _)| limiter)
+    }
+
+    /// Sets whether the target called `name` is considered when pacing. Does nothing if there is no
+    /// such target.
+    pub fn set_active(&mut self, name: &str, active: bool) {
+        if let Some(target) = self.targets.get_mut(name) {
+            target.1 = active;
+        }
+    }
+
+    /// Returns `true` if the target called `name` exists and is active.
+    pub fn is_active(&self, name: &str) -> bool {
+        self.targets.get(name).is_some_and(|(_, active)| *active)
+    }
+
+    /// The shortest frametime requested by the active targets, given the `detected` display
+    /// frametime, or `None` if no active target requests one.
+    pub fn resolve(&self, detected: Option<Duration>) -> Option<Duration> {
+        self.targets
+            .values()
+            .filter(|(_, active)| *active)
+            .filter_map(|(limiter, _)| limiter.resolve(detected))
+            .min()
+    }
+}
+
+/// A phase reference shared between apps, to phase-lock their frames to the same cadence, e.g. for
+/// synchronized multi-display setups.
+///
+/// Insert clones of the same [`FramepacePhase`] into each app; they share the reference through an
+/// [`Arc`]. The first app to finish sleeping sets the reference instant, and every app then aligns
+/// the end of its sleep to `phase + n * target_frametime`, so apps with the same target frametime
+/// present in lockstep. For apps in other processes, read the reference with
+/// [`FramepacePhase::get`] and share it externally, then [`FramepacePhase::set`] it on the other
+/// side. The reference is ignored while [`Limiter::AudioLocked`] provides its own phase. Native
+/// only.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepacePhase(Arc<Mutex<Option<Instant>>>);
+
+impl FramepacePhase {
+    /// Returns the reference instant frames are aligned to, if one was set.
+    pub fn get(&self) -> Option<Instant> {
+        self.0.try_lock().ok().and_then(|phase| *phase)
+    }
+
+    /// Sets the reference instant frames are aligned to.
+    pub fn set(&self, phase: Instant) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = Some(phase);
+        }
+    }
+
+    /// Clears the reference instant, the next app to finish sleeping sets a new one.
+    pub fn clear(&self) {
+        if let Ok(mut current) = self.0.lock() {
+            *current = None;
+        }
+    }
+
+    /// Sets the reference instant to `now` if none is set yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn set_if_unset(&self, now: Instant) {
+        if let Ok(mut current) = self.0.try_lock() {
+            current.get_or_insert(now);
+        }
+    }
+}
+
+impl ProxySlot for FramepacePhase {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>> {
+        &proxy.shared_phase
+    }
+}
+
+/// Records when each frame started, started sleeping, and finished sleeping, e.g. to inspect the
+/// pacing in a trace viewer with [`FramepaceTimeline::to_chrome_trace`].
+///
+/// Insert this resource to start recording, and remove it to stop. Only the last `capacity` frames
+/// are kept. The recording is shared between clones of the resource, so a clone can be kept to
+/// export the recording at any time. Native only, the limiter never sleeps on the web.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Resource)]
+pub struct FramepaceTimeline {
+    frames: Arc<Mutex<VecDeque<TimelineFrame>>>,
+    capacity: usize,
+}
+
+/// A frame recorded by the [`FramepaceTimeline`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimelineFrame {
+    /// When the frame started, at the end of the previous sleep.
+    pub frame_start: Instant,
+    /// When the limiter started sleeping.
+    pub sleep_start: Instant,
+    /// When the limiter finished sleeping.
+    pub sleep_end: Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FramepaceTimeline {
+    /// Creates a timeline keeping the last `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        FramepaceTimeline {
+            frames: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Returns the recorded frames, oldest first.
+    pub fn frames(&self) -> Vec<TimelineFrame> {
+        self.frames
+            .lock()
+            .map(|frames| frames.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Clears the recorded frames.
+    pub fn clear(&self) {
+        if let Ok(mut frames) = self.frames.lock() {
+            frames.clear();
+        }
+    }
+
+    /// Serializes the recorded frames to the Chrome trace event JSON format, which can be opened in
+    /// e.g. Perfetto, or `chrome://tracing`.
+    ///
+    /// Every frame emits two complete (`"ph": "X"`) events on `pid` and `tid` 0: a `"work"` event
+    /// from `frame_start` to `sleep_start`, and a `"sleep"` event from `sleep_start` to `sleep_end`.
+    /// Their `ts` and `dur` are in microseconds, relative to the start of the oldest recorded frame.
+    /// Each event has an `args.frame` counting the recorded frames from `0`.
+    pub fn to_chrome_trace(&self) -> String {
+        let frames = self.frames();
+        let Some(origin) = frames.first().map(|frame| frame.frame_start) else {
+            return String::from(r#"{"traceEvents":[]}"#);
+        };
+        let micros = |instant: Instant| instant.saturating_duration_since(origin).as_micros();
+        let events: Vec<String> = frames
+            .iter()
+            .enumerate()
+            .flat_map(|(index, frame)| {
+                [
+                    ("work", frame.frame_start, frame.sleep_start),
+                    ("sleep", frame.sleep_start, frame.sleep_end),
+                ]
+                .map(|(name, start, end)| {
+                    format!(
+                        r#"{{"name":"{name}","ph":"X","ts":{},"dur":{},"pid":0,"tid":0,"args":{{"frame":{index}}}}}"#,
+                        micros(start),
+                        end.saturating_duration_since(start).as_micros(),
+                    )
+                })
+            })
+            .collect();
+        format!(r#"{{"traceEvents":[{}]}}"#, events.join(","))
+    }
+
+    fn record(&self, frame: TimelineFrame) {
+        if let Ok(mut frames) = self.frames.try_lock() {
+            while frames.len() >= self.capacity.max(1) {
+                frames.pop_front();
+            }
+            if self.capacity > 0 {
+                frames.push_back(frame);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ProxySlot for FramepaceTimeline {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>> {
+        &proxy.timeline
+    }
+}
+
+/// The measurements a [`PacingAlgorithm`] decides the sleep duration from.
+#[derive(Debug, Clone, Copy)]
+pub struct PacingInput<'a> {
+    /// The time spent working on the current frame so far.
+    pub frame_time: Duration,
+    /// The target frametime.
+    pub target: Duration,
+    /// The oversleep to compensate for, smoothed according to
+    /// [`FramepaceSettings::oversleep_smoothing`].
+    pub oversleep: Duration,
+    /// The stats of the previous frames, including the [`FramePaceStats::history`] if enabled.
+    pub stats: &'a FramePaceStats,
+}
+
+/// Decides how long the limiter sleeps at the end of each frame, e.g. to implement a PID
+/// controller or a predictive pacer. Insert a [`FramepacePacing`] resource to use an algorithm.
+///
+/// [`PacingAlgorithm::sleep_time`] is called once per limited frame, in the render world, right
+/// before the limiter sleeps, so it runs on the render thread when pipelined rendering is enabled.
+/// It should be fast and must not block. The algorithm may keep state between frames. Its result
+/// still goes through [`FramepaceSettings::reserve_fraction`], the [`FramepaceSleepHook`], and
+/// [`FramepaceSettings::max_frame_sleep`]. The algorithm is not called while frames are aligned to
+/// a phase, see [`Limiter::AudioLocked`] and [`FramepacePhase`].
+pub trait PacingAlgorithm: Send + Sync + 'static {
+    /// Returns how long to sleep before starting the next frame.
+    fn sleep_time(&mut self, input: &PacingInput) -> Duration;
+}
+
+/// The default [`PacingAlgorithm`], sleeping for the rest of the target frametime, shortened by the
+/// oversleep of the previous frames.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultPacing;
+
+impl PacingAlgorithm for DefaultPacing {
+    fn sleep_time(&mut self, input: &PacingInput) -> Duration {
+        input
+            .target
+            .saturating_sub(input.frame_time + input.oversleep)
+    }
+}
+
+/// Replaces the pacing algorithm of the limiter with a [`PacingAlgorithm`]. Remove the resource to
+/// go back to [`DefaultPacing`]. Native only, the limiter never sleeps on the web.
+#[derive(Clone, Resource)]
+pub struct FramepacePacing(pub Arc<Mutex<dyn PacingAlgorithm>>);
+
+impl FramepacePacing {
+    /// Uses the provided pacing `algorithm`.
+    pub fn new(algorithm: impl PacingAlgorithm) -> Self {
+        FramepacePacing(Arc::new(Mutex::new(algorithm)))
+    }
+}
+
+opaque_debug!(FramepacePacing);
+
+impl ProxySlot for FramepacePacing {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>> {
+        &proxy.pacing
+    }
+}
+
+/// A callback reporting how close each frame came to its budget, e.g. to log frames that nearly
+/// missed the target.
+///
+/// When this resource is present, the hook is called every limited frame, right before the limiter
+/// sleeps, with the frame's work time, the target frametime, and the remaining budget, i.e. the
+/// part of the target that is left to sleep. The work time is measured at the
+/// [`MeasurementPoint::Cleanup`], regardless of [`FramepaceSettings::measurement_point`]. The hook
+/// runs in the render world, keep it short.
+#[derive(Clone, Resource)]
+pub struct FramepaceBudgetHook(pub Arc<BudgetHookFn>);
+
+/// The function of a [`FramepaceBudgetHook`], called with `(work_time, target, remaining_budget)`.
+pub type BudgetHookFn = dyn Fn(Duration, Duration, Duration) + Send + Sync;
+
+impl FramepaceBudgetHook {
+    /// Creates a hook from the provided function, called with `(work_time, target,
+    /// remaining_budget)`.
+    pub fn new(hook: impl Fn(Duration, Duration, Duration) + Send + Sync + 'static) -> Self {
+        FramepaceBudgetHook(Arc::new(hook))
+    }
+}
+
+opaque_debug!(FramepaceBudgetHook);
+
+impl ProxySlot for FramepaceBudgetHook {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>> {
+        &proxy.budget_hook
+    }
+}
+
+impl ProxySlot for FramepaceSleepHook {
+    fn slot(proxy: &FramepaceSettingsProxy) -> &Mutex<Option<Self>> {
+        &proxy.sleep_hook
+    }
+}
+
+/// Timing of a single frame, sent every frame when [`FramepaceSettings::frame_timing_events`] is
+/// enabled, e.g. for recording tools.
+///
+/// One event is sent per update of the main app, from the latest measurements of the render world.
+/// Like any bevy event, these must be read every frame or they will be dropped.
+#[derive(Debug, Clone, Event)]
+pub struct FrameTimingEvent {
+    /// The time spent on the frame, excluding the time spent sleeping.
+    pub frametime: Duration,
+    /// The target frametime, or `None` if limiting was disabled.
+    pub target: Option<Duration>,
+    /// How long the frame overshot the target frametime.
+    pub oversleep: Duration,
+    /// How long the limiter slept at the end of the frame.
+    pub sleep_time: Duration,
+}
+
+fn send_frame_timing_events(
+    settings: Res<FramepaceSettings>,
+    stats: Res<FramePaceStats>,
+    mut timings: EventWriter<FrameTimingEvent>,
+) {
+    if settings.frame_timing_events {
+        timings.send(FrameTimingEvent {
+            frametime: stats.frametime(),
+            target: stats.target_frametime(),
+            oversleep: stats.oversleep(),
+            sleep_time: stats.sleep_time(),
+        });
+    }
+}
+
+fn send_headroom_events(stats: Res<FramePaceStats>, mut events: EventWriter<FramepaceHeadroom>) {
+    if let Some(headroom_fraction) = stats.headroom.try_lock().ok().and_then(|mut h| h.take()) {
+        events.send(FramepaceHeadroom { headroom_fraction });
+    }
+}
+
+/// Exits the app once the limiter measured a number of frames, e.g. for deterministic benchmarks
+/// or screenshot automation.
+///
+/// When this resource is present, an [`AppExit`] event is sent once
+/// [`FramePaceStats::measured_frames`] reaches `frames`. Frames that were not measured, e.g. while
+/// the limiter is off, don't count, see [`FramePaceStats::frame_count`] to count every frame.
+#[derive(Debug, Clone, Resource)]
+pub struct FramepaceExitAfter {
+    /// The number of measured frames after which the app exits.
+    pub frames: u32,
+    /// The event sent to exit the app, [`AppExit::Success`] by default.
+    pub exit: AppExit,
+}
+
+impl FramepaceExitAfter {
+    /// Exits the app successfully after `frames` measured frames.
+    pub fn new(frames: u32) -> Self {
+        FramepaceExitAfter {
+            frames,
+            exit: AppExit::Success,
+        }
+    }
+
+    /// Exits with the provided exit `code` instead, see [`AppExit::from_code`].
+    pub fn with_exit_code(mut self, code: u8) -> Self {
+        self.exit = AppExit::from_code(code);
+        self
+    }
+}
+
+fn exit_after_frames(
+    exit_after: Option<Res<FramepaceExitAfter>>,
+    stats: Res<FramePaceStats>,
+    mut exit: EventWriter<AppExit>,
+    mut sent: Local<bool>,
+) {
+    if let Some(exit_after) = exit_after {
+        if !*sent && stats.measured_frames() >= exit_after.frames {
+            exit.send(exit_after.exit.clone());
+            *sent = true;
+        }
+    }
+}
+
+fn send_stall_events(stats: Res<FramePaceStats>, mut stalled: EventWriter<FramepaceStalled>) {
+    if let Some(missed_frames) = stats.stall.try_lock().ok().and_then(|mut s| s.take()) {
+        stalled.send(FramepaceStalled { missed_frames });
+    }
+}
+
+/// Configures the framelimiting technique for the app.
+///
+/// The default is the [`Limiter::platform_default`].
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum Limiter {
+    /// Uses the window's refresh rate to set the frametime limit, updating when the window changes
+    /// monitors. Frames are not limited until the refresh rate is first detected, see
+    /// [`FramePaceStats::awaiting_detection`].
+    Auto,
+    /// Uses the window's refresh rate to set the frametime limit like [`Limiter::Auto`], but never
+    /// targets a frametime shorter than the provided one. This caps the framerate on fast displays,
+    /// e.g. `AutoCapped(Duration::from_secs_f64(1.0 / 120.0))` runs at 120 fps on a 240 Hz monitor,
+    /// while still following slower displays down, running at 60 fps on a 60 Hz monitor. If the
+    /// refresh rate can't be detected, the provided frametime is used.
+    AutoCapped(Duration),
+    /// Set a fixed manual frametime limit. This should be greater than the monitors frametime
+    /// (`1.0 / monitor frequency`).
+    Manual(Duration),
+    /// Paces to a submultiple of the window's refresh rate, stepping the framerate down when the
+    /// app can't keep up, and back up when there is headroom. See [`AdaptiveSettings`].
+    Adaptive(AdaptiveSettings),
+    /// Paces the app to a multiple of an audio block period, for audio-visual sync in e.g. rhythm
+    /// games. The target frametime is `block * multiple`.
+    ///
+    /// If a `phase` is provided, frames are additionally aligned to start at `phase + n * block *
+    /// multiple`, where `phase` is the instant of any audio callback. Without a phase reference,
+    /// frames are paced to the target interval like [`Limiter::Manual`].
+    AudioLocked {
+        /// The period of the audio buffer callback.
+        block: Duration,
+        /// The number of audio blocks per frame.
+        multiple: u32,
+        /// The instant of a reference audio callback to align frames to.
+        phase: Option<Instant>,
+    },
+    /// Uses the window's refresh rate like [`Limiter::Auto`] while the user is providing input, and
+    /// smoothly ramps the framerate down once input stops. See [`IdleRampSettings`].
+    IdleRamp(IdleRampSettings),
+    /// Paces to a smoothed percentile of the recent frametimes, never faster than the window's
+    /// refresh rate. This keeps the cadence consistent by following the slowest recent frames,
+    /// trading peak framerate for even pacing. See [`ConsistentSettings`].
+    Consistent(ConsistentSettings),
+    /// Uses the target frametime returned by the [`FramepaceCustomLimiter`] resource every frame.
+    Custom,
+    /// Disables frame limiting
+    Off,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Limiter::platform_default()
+    }
+}
+
+impl Limiter {
+    /// [`Limiter::Manual`] preset limiting the framerate to 30 fps.
+    pub const FPS_30: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 30));
+    /// [`Limiter::Manual`] preset limiting the framerate to 60 fps.
+    pub const FPS_60: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 60));
+    /// [`Limiter::Manual`] preset limiting the framerate to 90 fps.
+    pub const FPS_90: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 90));
+    /// [`Limiter::Manual`] preset limiting the framerate to 120 fps.
+    pub const FPS_120: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 120));
+    /// [`Limiter::Manual`] preset limiting the framerate to 144 fps.
+    pub const FPS_144: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 144));
+    /// [`Limiter::Manual`] preset limiting the framerate to 165 fps.
+    pub const FPS_165: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 165));
+    /// [`Limiter::Manual`] preset limiting the framerate to 240 fps.
+    pub const FPS_240: Limiter = Limiter::Manual(Duration::from_nanos(1_000_000_000 / 240));
+    /// [`Limiter::Manual`] preset limiting the framerate to the NTSC film rate of 23.976
+    /// (24000/1001) fps.
+    pub const FPS_23_976: Limiter = Limiter::from_ratio(24_000, 1_001);
+    /// [`Limiter::Manual`] preset limiting the framerate to the NTSC rate of 29.97 (30000/1001) fps.
+    pub const FPS_29_97: Limiter = Limiter::from_ratio(30_000, 1_001);
+    /// [`Limiter::Manual`] preset limiting the framerate to the NTSC rate of 59.94 (60000/1001) fps.
+    pub const FPS_59_94: Limiter = Limiter::from_ratio(60_000, 1_001);
+    /// All integer `FPS_*` presets, from slowest to fastest, e.g. for listing them in a settings
+    /// menu.
+    pub const PRESETS: [Limiter; 7] = [
+        Limiter::FPS_30,
+        Limiter::FPS_60,
+        Limiter::FPS_90,
+        Limiter::FPS_120,
+        Limiter::FPS_144,
+        Limiter::FPS_165,
+        Limiter::FPS_240,
+    ];
+
+    /// Returns `true` if the [`Limiter`] is enabled.
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Limiter::Off)
+    }
+
+    /// Returns `true` if the [`Limiter`] needs the display refresh rate.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+    fn follows_display(&self) -> bool {
+        matches!(
+            self,
+            Limiter::Auto
+                | Limiter::AutoCapped(_)
+                | Limiter::Adaptive(_)
+                | Limiter::IdleRamp(_)
+                | Limiter::Consistent(_)
+        )
+    }
+
+    /// Returns the target frametime of this [`Limiter`], given the `detected` frametime of the
+    /// display.
+    ///
+    /// [`Limiter::Auto`], [`Limiter::Adaptive`], and [`Limiter::IdleRamp`] resolve to the `detected`
+    /// frametime, if any. For [`Limiter::Adaptive`], this is the fastest target the limiter will
+    /// step up to, for [`Limiter::IdleRamp`] it is the target while the user is active.
+    /// [`Limiter::Consistent`] also resolves to the `detected` frametime, the shortest target it
+    /// will use.
+    /// [`Limiter::AutoCapped`] resolves to the longer of the `detected` and capped frametimes.
+    /// Returns `None` if the limiter is [`Limiter::Off`], or [`Limiter::Custom`], whose target is
+    /// computed by the [`FramepaceCustomLimiter`].
+    pub fn resolve(&self, detected: Option<Duration>) -> Option<Duration> {
+        match self {
+            Limiter::Auto
+            | Limiter::Adaptive(_)
+            | Limiter::IdleRamp(_)
+            | Limiter::Consistent(_) => detected,
+            Limiter::AutoCapped(cap) => Some(detected.map_or(*cap, |detected| detected.max(*cap))),
+            Limiter::Manual(frametime) => Some(*frametime),
+            Limiter::AudioLocked {
+                block, multiple, ..
+            } => Some(*block * *multiple),
+            Limiter::Custom | Limiter::Off => None,
+        }
+    }
+
+    /// The [`Limiter`] used by default on the target platform:
+    ///
+    /// - On the web, [`Limiter::Off`], because the browser already limits the framerate, and frame
+    ///   limiting would compete with it.
+    /// - On Android and iOS, [`Limiter::AutoCapped`] at 60 fps, to save battery on high refresh
+    ///   rate displays.
+    /// - On desktop platforms, [`Limiter::Auto`].
+    pub fn platform_default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Limiter::Off
+        } else if cfg!(any(target_os = "android", target_os = "ios")) {
+            Limiter::AutoCapped(Duration::from_secs_f64(1.0 / 60.0))
+        } else {
+            Limiter::Auto
+        }
+    }
+
+    /// Estimates the fraction of time, in the range `0.0..=1.0`, the app spends working rather than
+    /// sleeping with this limiter, if each frame takes `work_time`, e.g.
+    /// [`FramePaceStats::work_time`], on a display with the `detected` refresh frametime.
+    ///
+    /// This is a rough estimate, comparing the work time to the target frametime. Limiters that
+    /// don't resolve to a target, such as [`Limiter::Off`], are assumed to never sleep.
+    pub fn estimated_cpu_fraction(&self, work_time: Duration, detected: Option<Duration>) -> f64 {
+        match self.resolve(detected) {
+            Some(target) if !target.is_zero() => {
+                (work_time.as_secs_f64() / target.as_secs_f64()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Estimates how much of the app's time, in the range `-1.0..=1.0`, switching from this limiter
+    /// to the `candidate` frees up, e.g. to show the power savings of a framerate cap in a settings
+    /// menu. Negative values mean the `candidate` keeps the app busier. See
+    /// [`Limiter::estimated_cpu_fraction`].
+    pub fn estimated_cpu_savings(
+        &self,
+        candidate: &Limiter,
+        work_time: Duration,
+        detected: Option<Duration>,
+    ) -> f64 {
+        self.estimated_cpu_fraction(work_time, detected)
+            - candidate.estimated_cpu_fraction(work_time, detected)
+    }
+
+    /// Constructs a new [`Limiter`] from the provided `framerate`.
+    pub fn from_framerate(framerate: f64) -> Self {
+        Limiter::Manual(Duration::from_secs_f64(1.0 / framerate))
+    }
+
+    /// Constructs a new [`Limiter::Manual`] running at exactly `numerator / denominator` fps, e.g.
+    /// `from_ratio(24_000, 1_001)` for 23.976 fps.
+    ///
+    /// Unlike [`Limiter::from_framerate`], the frametime is computed with integer arithmetic, so it
+    /// is within half a nanosecond of the exact rational frametime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `numerator` is zero.
+    pub const fn from_ratio(numerator: u32, denominator: u32) -> Self {
+        assert!(numerator > 0, "the framerate numerator must not be zero");
+        let numerator = numerator as u128;
+        let nanos = (1_000_000_000 * denominator as u128 + numerator / 2) / numerator;
+        Limiter::Manual(Duration::from_nanos(nanos as u64))
+    }
+
+    /// Constructs a new [`Limiter::Manual`] with a frametime of exactly `nanos` nanoseconds.
+    ///
+    /// The frametime is stored and used as is, without a round trip through a framerate. Only
+    /// [`FramepaceSettings::snap_to_integer_fps`] changes it.
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Limiter::Manual(Duration::from_nanos(nanos))
+    }
+}
+
+impl std::fmt::Display for Limiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limiter::Auto => write!(f, "Auto"),
+            Limiter::AutoCapped(t) => write!(f, "Auto (max {:.2} fps)", 1.0 / t.as_secs_f64()),
+            Limiter::Manual(t) => write!(f, "{:.2} fps", 1.0 / t.as_secs_f64()),
+            Limiter::Adaptive(_) => write!(f, "Adaptive"),
+            Limiter::IdleRamp(_) => write!(f, "Idle ramp"),
+            Limiter::Consistent(_) => write!(f, "Consistent"),
+            Limiter::Custom => write!(f, "Custom"),
+            Limiter::AudioLocked {
+                block, multiple, ..
+            } => write!(
+                f,
+                "Audio locked ({:.2} fps)",
+                1.0 / (*block * *multiple).as_secs_f64()
+            ),
+            Limiter::Off => write!(f, "Off"),
+        }
+    }
+}
+
+impl std::str::FromStr for Limiter {
+    type Err = ParseLimiterError;
+
+    /// Parses `"auto"`, `"off"`, a framerate such as `"144"` or `"144 fps"`, or a frametime in
+    /// milliseconds such as `"16.6ms"`. Matching is case-insensitive. Values that don't result in a
+    /// positive frametime that fits a [`Duration`] are rejected.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_ascii_lowercase();
+        let error = || ParseLimiterError(s.to_string());
+        let number = |value: &str| value.trim().parse::<f64>().map_err(|_| error());
+        let frametime = |secs: f64| {
+            Duration::try_from_secs_f64(secs)
+                .ok()
+                .filter(|frametime| !frametime.is_zero())
+                .map(Limiter::Manual)
+                .ok_or_else(error)
+        };
+        match input.as_str() {
+            "auto" => Ok(Limiter::Auto),
+            "off" => Ok(Limiter::Off),
+            _ => {
+                if let Some(millis) = input.strip_suffix("ms") {
+                    frametime(number(millis)? / 1_000.0)
+                } else {
+                    let fps = input.strip_suffix("fps").unwrap_or(&input);
+                    frametime(1.0 / number(fps)?)
+                }
+            }
+        }
+    }
+}
+
+/// Error returned when parsing a [`Limiter`] from a string fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLimiterError(String);
+
+impl std::fmt::Display for ParseLimiterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid limiter `{}`, expected `auto`, `off`, a framerate such as `144`, or a \
+            frametime such as `16.6ms`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseLimiterError {}
+
+/// Tuning parameters for [`Limiter::Adaptive`].
+///
+/// The adaptive limiter targets the display refresh rate divided by an integer divisor, e.g. 144,
+/// 72, 48 fps on a 144 Hz display. A frame is considered *slow* when its frametime exceeds the
+/// current target multiplied by `step_down_threshold`, and *fast* when its frametime is below the
+/// next faster target multiplied by `step_up_threshold`. After `consecutive_frames` slow or fast
+/// frames in a row, the divisor is increased or decreased respectively. Once the divisor changes,
+/// it is held for at least `cooldown` before it can change again.
+///
+/// Lowering `consecutive_frames` and `cooldown` makes the limiter react faster to load changes, at
+/// the risk of oscillating between framerates under uneven load. Moving the thresholds away from
+/// `1.0` makes steps less likely; keep `step_up_threshold` well below `1.0` so that stepping up
+/// doesn't immediately cause frames to become slow again.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct AdaptiveSettings {
+    /// Ratio of the current target frametime above which a frame counts as slow.
+    pub step_down_threshold: f64,
+    /// Ratio of the next faster target frametime below which a frame counts as fast.
+    pub step_up_threshold: f64,
+    /// The number of consecutive slow or fast frames required to step the framerate.
+    pub consecutive_frames: u32,
+    /// The minimum time between two framerate steps.
+    pub cooldown: Duration,
+    /// The largest divisor of the refresh rate the limiter will step down to.
+    pub max_divisor: u32,
+}
+impl Default for AdaptiveSettings {
+    fn default() -> Self {
+        AdaptiveSettings {
+            step_down_threshold: 1.05,
+            step_up_threshold: 0.8,
+            consecutive_frames: 30,
+            cooldown: Duration::from_secs(1),
+            max_divisor: 4,
+        }
+    }
+}
+impl AdaptiveSettings {
+    /// Settings tuned for battery life, selecting the highest refresh submultiple the app can
+    /// comfortably sustain.
+    ///
+    /// A frame only counts as fast when it would fit the next faster target with 40% headroom, so
+    /// the limiter settles on a framerate with spare capacity rather than one it barely reaches.
+    /// It steps down as soon as frames miss the current target for a second at 60 fps, and
+    /// re-evaluates the framerate at most every 5 seconds, so it does not oscillate between
+    /// submultiples under uneven load.
+    pub fn power_saving() -> Self {
+        AdaptiveSettings {
+            step_down_threshold: 1.0,
+            step_up_threshold: 0.6,
+            consecutive_frames: 60,
+            cooldown: Duration::from_secs(5),
+            max_divisor: 4,
+        }
+    }
+}
+
+/// Tuning parameters for [`Limiter::IdleRamp`].
+///
+/// Once no input has been received for `delay`, the framerate ramps down from the display refresh
+/// rate to the `floor` over the `decay` duration, and snaps back up as soon as input is received.
+/// The ramp is interpolated in framerate, `exponent` shapes the curve.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct IdleRampSettings {
+    /// How long after the last input the framerate starts ramping down.
+    pub delay: Duration,
+    /// How long it takes to ramp the framerate down to the `floor`.
+    pub decay: Duration,
+    /// The frametime reached at the end of the ramp.
+    pub floor: Duration,
+    /// Shapes the ramp curve. `1.0` lowers the framerate linearly, larger values hold a high
+    /// framerate for longer before dropping, smaller values drop quickly at first.
+    pub exponent: f32,
+}
+impl Default for IdleRampSettings {
+    fn default() -> Self {
+        IdleRampSettings {
+            delay: Duration::from_secs(1),
+            decay: Duration::from_secs(5),
+            floor: Duration::from_secs_f64(1.0 / 10.0),
+            exponent: 1.0,
+        }
+    }
+}
+impl IdleRampSettings {
+    /// Returns the target frametime after `idle` time without input, given the `active` target
+    /// frametime.
+    ///
+    /// The result is always between `active` and the `floor`. A `floor` faster than `active` is
+    /// ignored, and a non-positive or non-finite `exponent` is treated as `1.0`.
+    pub fn frametime(&self, active: Duration, idle: Duration) -> Duration {
+        let exponent = match self.exponent {
+            exponent if exponent.is_finite() && exponent > 0.0 => exponent as f64,
+            _ => 1.0,
+        };
+        let progress = idle.saturating_sub(self.delay).as_secs_f64()
+            / self.decay.as_secs_f64().max(f64::EPSILON);
+        let progress = progress.clamp(0.0, 1.0).powf(exponent);
+        let floor = self.floor.max(active);
+        let active_fps = 1.0 / active.as_secs_f64();
+        let floor_fps = 1.0 / floor.as_secs_f64();
+        let fps = active_fps + (floor_fps - active_fps) * progress;
+        let frametime = match 1.0 / fps {
+            secs if secs.is_nan() => active,
+            secs => Duration::try_from_secs_f64(secs).unwrap_or(floor),
+        };
+        frametime.clamp(active, floor)
+    }
+}
+
+/// Tuning parameters for [`Limiter::Consistent`].
+///
+/// Each frame, the `percentile` of the frametimes of the last `window` frames is computed, and the
+/// target frametime moves towards it by the `smoothing` factor. The target never drops below the
+/// display refresh frametime.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct ConsistentSettings {
+    /// The percentile of recent frametimes to target, in the range `0.0..=1.0`.
+    pub percentile: f64,
+    /// The number of recent frames the percentile is computed over.
+    pub window: usize,
+    /// How far the target moves towards the percentile each frame, in the range `0.0..=1.0`. Lower
+    /// values give a steadier target that reacts slower to load changes.
+    pub smoothing: f64,
+}
+impl Default for ConsistentSettings {
+    fn default() -> Self {
+        ConsistentSettings {
+            percentile: 0.95,
+            window: 120,
+            smoothing: 0.05,
+        }
+    }
+}
+
+/// The instant of the last user input, used by [`Limiter::IdleRamp`].
+#[derive(Debug, Resource)]
+struct LastInput(Instant);
+impl Default for LastInput {
+    fn default() -> Self {
+        LastInput(Instant::now())
+    }
+}
+
+fn track_input(
+    mut last_input: ResMut<LastInput>,
+    mut keyboard: EventReader<bevy_input::keyboard::KeyboardInput>,
+    mut mouse_buttons: EventReader<bevy_input::mouse::MouseButtonInput>,
+    mut mouse_wheel: EventReader<bevy_input::mouse::MouseWheel>,
+    mut touch: EventReader<bevy_input::touch::TouchInput>,
+    mut cursor: EventReader<CursorMoved>,
+) {
+    let received = keyboard.read().count()
+        + mouse_buttons.read().count()
+        + mouse_wheel.read().count()
+        + touch.read().count()
+        + cursor.read().count();
+    if received > 0 {
+        last_input.0 = Instant::now();
+    }
+}
+
+/// Render world state of the [`Limiter::Adaptive`] limiter.
+#[derive(Debug, Clone, Resource)]
+struct AdaptiveState {
+    /// The refresh frametime is multiplied by this value to get the target frametime.
+    divisor: u32,
+    /// Number of consecutive slow frames.
+    slow_frames: u32,
+    /// Number of consecutive fast frames.
+    fast_frames: u32,
+    /// When the divisor was last changed.
+    last_step: Instant,
+}
+impl Default for AdaptiveState {
+    fn default() -> Self {
+        AdaptiveState {
+            divisor: 1,
+            slow_frames: 0,
+            fast_frames: 0,
+            last_step: Instant::now(),
+        }
+    }
+}
+impl AdaptiveState {
+    /// Records the latest `frame_time`, and returns the frametime the limiter should target.
+    fn update(
+        &mut self,
+        settings: &AdaptiveSettings,
+        refresh_frametime: Duration,
+        frame_time: Duration,
+    ) -> Duration {
+        let target = refresh_frametime * self.divisor;
+        let faster_target = refresh_frametime * self.divisor.saturating_sub(1).max(1);
+
+        if frame_time > target.mul_f64(settings.step_down_threshold) {
+            self.slow_frames += 1;
+        } else {
+            self.slow_frames = 0;
+        }
+        if self.divisor > 1 && frame_time < faster_target.mul_f64(settings.step_up_threshold) {
+            self.fast_frames += 1;
+        } else {
+            self.fast_frames = 0;
+        }
+
+        if self.last_step.elapsed() >= settings.cooldown {
+            let max_divisor = settings.max_divisor.max(1);
+            if self.slow_frames >= settings.consecutive_frames && self.divisor < max_divisor {
+                self.step(self.divisor + 1);
+            } else if self.divisor > 1 && self.fast_frames >= settings.consecutive_frames {
+                self.step(self.divisor - 1);
+            }
+        }
+
+        refresh_frametime * self.divisor
+    }
+
+    fn step(&mut self, divisor: u32) {
+        self.divisor = divisor;
+        self.slow_frames = 0;
+        self.fast_frames = 0;
+        self.last_step = Instant::now();
+    }
+}
+
+/// Render world state of the [`Limiter::Consistent`] limiter.
+#[derive(Debug, Default, Resource)]
+struct ConsistentState {
+    /// The frametimes of the recent frames.
+    frametimes: VecDeque<Duration>,
+    /// The smoothed target frametime.
+    target: Option<Duration>,
+}
+impl ConsistentState {
+    /// Records the latest `frame_time`, and returns the frametime the limiter should target.
+    ///
+    /// Before the refresh rate is detected, `refresh_frametime` is zero, and frames are not
+    /// limited, like [`Limiter::Auto`].
+    fn update(
+        &mut self,
+        settings: &ConsistentSettings,
+        refresh_frametime: Duration,
+        frame_time: Duration,
+    ) -> Duration {
+        if refresh_frametime.is_zero() {
+            *self = ConsistentState::default();
+            return Duration::ZERO;
+        }
+        while self.frametimes.len() >= settings.window.max(1) {
+            self.frametimes.pop_front();
+        }
+        self.frametimes.push_back(frame_time);
+
+        let mut sorted: Vec<_> = self.frametimes.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() - 1) as f64 * settings.percentile.clamp(0.0, 1.0)).round();
+        let percentile = sorted[index as usize];
+
+        let smoothing = settings.smoothing.clamp(0.0, 1.0);
+        let target = match self.target {
+            Some(target) => Duration::from_secs_f64(
+                target.as_secs_f64()
+                    + (percentile.as_secs_f64() - target.as_secs_f64()) * smoothing,
+            ),
+            None => percentile,
+        }
+        .max(refresh_frametime);
+        self.target = Some(target);
+        target
+    }
+}
+
+/// Current frametime limit based on settings and monitor refresh rate.
+///
+/// This is the target computed from the applied limiter during [`Update`], e.g. to show the
+/// effective cap in a settings menu. It is [`Duration::ZERO`] until a target is known. The
+/// resource is shared with the render app, and can be read from either. It is only written when
+/// the target is recomputed in the main world: limiters such as [`Limiter::Adaptive`] and
+/// [`Limiter::Consistent`] derive their per-frame target from it inside the limiter without
+/// writing it back, see [`FramePaceStats::target_frametime`] for the frametime the limiter
+/// actually paced the last frame to.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct FrametimeLimit {
+    limit: Arc<Mutex<Duration>>,
+    /// The last written limit in nanoseconds, read while the limiter holds the lock.
+    last: Arc<AtomicU64>,
+    /// The limit is zero because the applied limiter doesn't limit, e.g. [`Limiter::Custom`]
+    /// without a [`FramepaceCustomLimiter`], rather than because of a misconfiguration.
+    unlimited: Arc<AtomicBool>,
+}
+
+impl FrametimeLimit {
+    /// Returns the current frametime limit, or the last known value while it is being used by the
+    /// limiter.
+    pub fn current(&self) -> Duration {
+        match self.limit.try_lock() {
+            Ok(limit) => *limit,
+            Err(_) => Duration::from_nanos(self.last.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Tracks the instant of the end of the previous frame.
+#[derive(Debug, Clone, Resource, Reflect)]
+pub struct FrameTimer {
+    sleep_end: Instant,
+    /// Whether the limiter was enabled during the previous frame.
+    enabled: bool,
+    /// The number of consecutive frames that missed the target frametime.
+    missed_frames: u32,
+    /// When the current frame was presented.
+    presented: Option<Instant>,
+    /// Whether a zero target frametime was already reported.
+    warned_zero_limit: bool,
+    /// The [`FrameCount`] of the last frame that was limited.
+    last_frame: Option<u32>,
+    /// Bitmask of the recent frames that exceeded the target frametime by a margin, the least
+    /// significant bit being the latest frame.
+    recent_misses: u64,
+    /// Moving average of the oversleep, see [`FramepaceSettings::oversleep_smoothing`].
+    smoothed_oversleep: Duration,
+    /// Whether the headroom is above [`FramepaceSettings::headroom_threshold`].
+    headroom_exceeded: bool,
+    /// Whether the previous frame was late and realigned to the schedule, see
+    /// [`FramepaceSettings::drop_late_frames`].
+    realigned: bool,
+    /// Whether a sleep clamped to [`FramepaceSettings::max_frame_sleep`] was already reported.
+    warned_sleep_clamp: bool,
+    /// The number of frames seen by the limiter, saturating.
+    frames: u32,
+    /// Tracks the accuracy of the sleeper itself, see [`SleepErrorTracker`].
+    #[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+    #[reflect(ignore)]
+    sleep_error: SleepErrorTracker,
+    /// Looks for another limiter slowing down frames, see [`ConflictTracker`].
+    #[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+    #[reflect(ignore)]
+    conflict: ConflictTracker,
+}
+
+/// Tracks the variance of the difference between requested and actual sleep durations, to tell
+/// when poor pacing is caused by the sleeper rather than by the app.
+#[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Default)]
+struct SleepErrorTracker {
+    /// Number of frames tracked, saturating at [`SleepErrorTracker::WARMUP_FRAMES`].
+    frames: u32,
+    /// Moving average of the sleep error, in seconds.
+    mean: f64,
+    /// Moving variance of the sleep error, in seconds squared.
+    variance: f64,
+    /// Whether the inaccurate sleeper was already reported.
+    warned: bool,
+}
+
+#[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+impl SleepErrorTracker {
+    /// Weight of the latest frame in the moving average and variance.
+    const SMOOTHING: f64 = 0.02;
+    /// Frames tracked before the sleeper can be reported as inaccurate.
+    const WARMUP_FRAMES: u32 = 120;
+    /// Standard deviation of the sleep error, relative to the target frametime, above which the
+    /// sleeper is reported as inaccurate.
+    const MAX_DEVIATION: f64 = 0.05;
+
+    /// Records a frame of the sleeper that requested to sleep for `requested` and slept for
+    /// `actual`, then warns once if the sleeper is inaccurate while the app has spare budget.
+    fn record(&mut self, requested: Duration, actual: Duration, work: Duration, target: Duration) {
+        let error = actual.as_secs_f64() - requested.as_secs_f64();
+        let delta = error - self.mean;
+        self.mean += Self::SMOOTHING * delta;
+        self.variance = (1.0 - Self::SMOOTHING) * (self.variance + Self::SMOOTHING * delta * delta);
+        self.frames = (self.frames + 1).min(Self::WARMUP_FRAMES);
+
+        let deviation = self.variance.sqrt();
+        if !self.warned
+            && self.frames >= Self::WARMUP_FRAMES
+            && work < target / 2
+            && deviation > target.as_secs_f64() * Self::MAX_DEVIATION
+        {
+            bevy_log::warn!(
+                "The limiter sleep is inaccurate ({:.3}ms deviation) although frames are well \
+                within budget. Consider raising the spin threshold with \
+                `SleepStrategy::SpinThreshold`, or switching to `SleepStrategy::Spin`.",
+                deviation * 1_000.0
+            );
+            self.warned = true;
+        }
+    }
+}
+/// Detects frames that are consistently longer than the target, so the limiter has nothing left to
+/// sleep, with a steadiness typical of something sleeping rather than of real work. This usually means
+/// another limiter is active, e.g. vsync at a lower refresh rate, bevy's `WinitSettings`, or a
+/// system calling `std::thread::sleep`.
+#[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+#[derive(Debug, Clone, Default)]
+struct ConflictTracker {
+    /// Number of consecutive suspicious frames, saturating at [`ConflictTracker::FRAMES`].
+    frames: u32,
+    /// Moving average of the frametime relative to the target.
+    mean: f64,
+    /// Moving variance of the frametime relative to the target.
+    variance: f64,
+    /// Whether the conflicting limiter was already reported.
+    warned: bool,
+}
+
+#[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+impl ConflictTracker {
+    /// Weight of the latest frame in the moving average and variance.
+    const SMOOTHING: f64 = 0.05;
+    /// Consecutive frames over the target before a conflicting limiter is reported.
+    const FRAMES: u32 = 180;
+    /// The frametime, relative to the target, above which a frame is suspicious.
+    const MIN_RATIO: f64 = 1.25;
+    /// Standard deviation of the relative frametime below which frames are considered paced by
+    /// something else.
+    const MAX_DEVIATION: f64 = 0.03;
+
+    /// Records a frame that worked for `work`, with a target frametime of `target`, and warns once
+    /// if frames look paced by another limiter.
+    fn record(&mut self, work: Duration, target: Duration) {
+        let ratio = work.as_secs_f64() / target.as_secs_f64();
+        if ratio < Self::MIN_RATIO {
+            self.frames = 0;
+            return;
+        }
+        if self.frames == 0 {
+            self.mean = ratio;
+            self.variance = 0.0;
+        }
+        let delta = ratio - self.mean;
+        self.mean += Self::SMOOTHING * delta;
+        self.variance = (1.0 - Self::SMOOTHING) * (self.variance + Self::SMOOTHING * delta * delta);
+        self.frames = (self.frames + 1).min(Self::FRAMES);
+
+        if !self.warned && self.frames >= Self::FRAMES && self.variance.sqrt() < Self::MAX_DEVIATION
+        {
+            bevy_log::warn!(
+                "Frames steadily take {:.2}ms, longer than the target of {:.2}ms. Another frame \
+                limiter may be active, e.g. vsync, `WinitSettings`, or a system that sleeps.",
+                work.as_secs_f64() * 1_000.0,
+                target.as_secs_f64() * 1_000.0
+            );
+            self.warned = true;
+        }
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        FrameTimer {
+            sleep_end: Instant::now(),
+            enabled: true,
+            missed_frames: 0,
+            presented: None,
+            warned_zero_limit: false,
+            last_frame: None,
+            recent_misses: 0,
+            smoothed_oversleep: Duration::ZERO,
+            headroom_exceeded: false,
+            realigned: false,
+            warned_sleep_clamp: false,
+            frames: 0,
+            #[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+            sleep_error: SleepErrorTracker::default(),
+            #[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+            conflict: ConflictTracker::default(),
+        }
+    }
+}
+
+/// The last frametime successfully detected from the display refresh rate, if any.
+///
+/// Windows can briefly disappear while being recreated, e.g. when toggling fullscreen. Keeping the
+/// last known good value avoids a transient pacing glitch while detection is unavailable. Native
+/// only.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Resource)]
+pub struct DetectedFrametime(pub Option<Duration>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DetectedFrametime {
+    /// Clears the last detected frametime, e.g. for a "rescan displays" button after the display
+    /// settings were changed in the OS.
+    ///
+    /// The refresh rate is re-detected on the next frame, and a [`RefreshRateChanged`] event is
+    /// sent once detection succeeds, even if the refresh rate didn't change. Until then, limiters
+    /// following the display behave as if the refresh rate was unknown.
+    pub fn force_refresh_detection(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// Detects the display refresh rate, without limiting the framerate.
+///
+/// This is a lightweight way to use the refresh rate detection of this crate on its own, e.g. to
+/// configure vsync or a simulation rate. No frame limiter is added, the detected frametime is
+/// available in the [`DetectedFrametime`] resource, and a [`RefreshRateChanged`] event is sent when
+/// it changes. If the [`FramepacePlugin`] is added too, it takes over detection, and keeps
+/// detecting even while its limiter doesn't follow the display. Native only.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+#[derive(Debug, Clone, Default)]
+pub struct RefreshRatePlugin;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+impl Plugin for RefreshRatePlugin {
+    fn build(&self, app: &mut App) {
+        // The `FramepacePlugin` detects the refresh rate itself, whichever plugin is added first.
+        app.init_resource::<DetectedFrametime>()
+            .init_resource::<RefreshRateRequested>()
+            .add_event::<RefreshRateChanged>()
+            .add_systems(
+                Update,
+                detect_refresh_rate.run_if(not(resource_exists::<FramepaceSettings>)),
+            );
+    }
+}
+
+/// Inserted by the [`RefreshRatePlugin`], so the [`FramepacePlugin`] keeps [`DetectedFrametime`] up
+/// to date even while its limiter doesn't follow the display.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+#[derive(Debug, Default, Resource)]
+struct RefreshRateRequested;
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn detect_refresh_rate(
+    winit: NonSend<WinitWindows>,
+    windows: Query<(Entity, &Window)>,
+    mut detected: ResMut<DetectedFrametime>,
+    mut refresh_changed: EventWriter<RefreshRateChanged>,
+    mut lost: Local<bool>,
+) {
+    let frametime = detect_frametime(winit, windows.iter(), RefreshRounding::default(), None);
+    update_detected_frametime(frametime, &mut detected, &mut lost, &mut refresh_changed);
+}
+
+/// Stores a successful detection in `detected`, sending a [`RefreshRateChanged`] event if it
+/// changed, or if detection recovered after failing, e.g. once a window lands on a valid monitor
+/// after its monitor was disconnected. On failure, the last known frametime is kept.
+///
+/// `detected` is only written when the frametime differs, so events that re-detect the same
+/// refresh rate, such as `WindowScaleFactorChanged`, don't trigger its change detection.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn update_detected_frametime(
+    frametime: Option<Duration>,
+    detected: &mut ResMut<DetectedFrametime>,
+    lost: &mut bool,
+    refresh_changed: &mut EventWriter<RefreshRateChanged>,
+) {
+    match frametime {
+        Some(frametime) => {
+            if detected.0 != Some(frametime) || *lost {
+                refresh_changed.send(RefreshRateChanged { frametime });
+            }
+            detected.set_if_neq(DetectedFrametime(Some(frametime)));
+            *lost = false;
+        }
+        None if detected.0.is_some() && !*lost => {
+            #[cfg(feature = "framepace_debug")]
+            bevy_log::info!(
+                "Refresh rate detection failed, keeping the last detected refresh rate"
+            );
+            *lost = true;
+        }
+        None => {}
+    }
+}
+
+/// Sent when the detected display refresh rate changes, including the initial detection.
+///
+/// The refresh rate is re-queried every frame while the limiter follows the display, or the
+/// `RefreshRatePlugin` is added, so this also catches changes initiated by the OS without any
+/// window event, such as a laptop lowering the panel refresh rate on battery. Native only.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Event)]
+pub struct RefreshRateChanged {
+    /// The frametime of the newly detected refresh rate.
+    pub frametime: Duration,
+}
+
+/// Warns once if a limiter is enabled on the web, where the limiter can't sleep.
+#[cfg(target_arch = "wasm32")]
+fn warn_unsupported_limiter(
+    settings: Res<FramepaceSettings>,
+    activity: Res<FramepaceActivity>,
+    occlusion: Res<WindowOcclusion>,
+    mut warned: Local<bool>,
+) {
+    if !*warned && settings.applied_limiter(&activity, &occlusion).is_enabled() {
+        bevy_log::warn!(
+            "Frame limiting is not supported on the web, the {} limiter has no effect. The browser \
+            already paces frames to the display with `requestAnimationFrame`.",
+            settings.applied_limiter(&activity, &occlusion)
+        );
+        *warned = true;
+    }
+}
+
+/// Warns once if winit runs in a reactive [`UpdateMode`](bevy_winit::UpdateMode) while the limiter
+/// is enabled.
+///
+/// In reactive mode, winit already waits for events or a timeout between frames, and the limiter
+/// sleeping on top of that double-waits, making frames longer than either wait alone.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn warn_reactive_update_mode(
+    winit_settings: Option<Res<bevy_winit::WinitSettings>>,
+    settings: Res<FramepaceSettings>,
+    activity: Res<FramepaceActivity>,
+    occlusion: Res<WindowOcclusion>,
+    mut warned: Local<bool>,
+) {
+    let Some(winit_settings) = winit_settings else {
+        return;
+    };
+    let reactive =
+        |mode: &bevy_winit::UpdateMode| matches!(mode, bevy_winit::UpdateMode::Reactive { .. });
+    if !*warned
+        && (reactive(&winit_settings.focused_mode) || reactive(&winit_settings.unfocused_mode))
+        && settings.applied_limiter(&activity, &occlusion).is_enabled()
+    {
+        bevy_log::warn!(
+            "winit is in a reactive update mode while frame limiting is enabled, both will wait \
+            between frames. Use `UpdateMode::Continuous` to let the limiter pace frames, or \
+            `Limiter::Off` to let winit wait."
+        );
+        *warned = true;
+    }
+}
+
+/// Run condition of [`detect_followed_refresh_rate`], `true` while the applied limiter follows
+/// the display, or always if the [`RefreshRatePlugin`] was added too.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn detects_refresh_rate(
+    settings: Res<FramepaceSettings>,
+    activity: Res<FramepaceActivity>,
+    occlusion: Res<WindowOcclusion>,
+    requested: Option<Res<RefreshRateRequested>>,
+) -> bool {
+    requested.is_some()
+        || settings
+            .applied_limiter(&activity, &occlusion)
+            .follows_display()
+}
+
+/// Detects the display refresh rate, see [`detects_refresh_rate`] for when it runs.
+///
+/// The refresh rate is polled rather than recomputed in response to window events, and the
+/// frametime limit is only committed when the detected value changes. Window events that don't
+/// change the refresh rate, such as `WindowScaleFactorChanged` when changing fractional display
+/// scaling, therefore never cause a pacing transient, while moving to another monitor or changing
+/// its refresh rate is still picked up on the next frame.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn detect_followed_refresh_rate(
+    settings: Res<FramepaceSettings>,
+    winit: NonSend<WinitWindows>,
+    windows: Query<(Entity, &Window)>,
+    mut detected: ResMut<DetectedFrametime>,
+    mut refresh_changed: EventWriter<RefreshRateChanged>,
+    mut lost: Local<bool>,
+) {
+    let frametime = detect_frametime(
+        winit,
+        windows.iter(),
+        settings.refresh_rounding,
+        settings.target_monitor.as_ref(),
+    );
+    update_detected_frametime(frametime, &mut detected, &mut lost, &mut refresh_changed);
+}
+
+/// Rounds `frametime` to the frametime of the nearest integer framerate, at least 1 fps, see
+/// [`FramepaceSettings::snap_to_integer_fps`]. A zero frametime is returned as is.
+#[cfg(not(target_arch = "wasm32"))]
+fn snap_to_integer_fps(frametime: Duration) -> Duration {
+    if frametime.is_zero() {
+        return frametime;
+    }
+    let fps = (1.0 / frametime.as_secs_f64()).round().max(1.0);
+    Duration::from_secs_f64(1.0 / fps)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::too_many_arguments)]
+fn get_display_refresh_rate(
+    settings: Res<FramepaceSettings>,
+    activity: Res<FramepaceActivity>,
+    occlusion: Res<WindowOcclusion>,
+    speed_scale: Res<FramepaceSpeedScale>,
+    frame_limit: Res<FrametimeLimit>,
+    detected: Res<DetectedFrametime>,
+    last_input: Res<LastInput>,
+    custom: Option<Res<FramepaceCustomLimiter>>,
+    targets: Option<Res<FramepaceTargets>>,
+    stats: Res<FramePaceStats>,
+) {
+    let limiter = settings.applied_limiter(&activity, &occlusion);
+    let targeted = targets
+        .filter(|_| limiter.is_enabled())
+        .filter(|_| settings.applied_reason(&activity, &occlusion) == LimiterReason::Configured)
+        .and_then(|targets| targets.resolve(detected.0));
+    let custom_missing = custom.is_none();
+    let resolved = targeted.or_else(|| match limiter {
+        // Without the resource, a custom limiter doesn't limit, and isn't waiting for anything.
+        Limiter::Custom => Some(custom.map_or(Duration::ZERO, |custom| (custom.0)())),
+        Limiter::Manual(frametime) if settings.snap_to_integer_fps => {
+            Some(snap_to_integer_fps(*frametime))
+        }
+        _ => limiter.resolve(detected.0),
+    });
+    stats.awaiting_detection.store(
+        resolved.is_none() && limiter.is_enabled(),
+        Ordering::Relaxed,
+    );
+    frame_limit.unlimited.store(
+        targeted.is_none() && matches!(limiter, Limiter::Custom) && custom_missing,
+        Ordering::Relaxed,
+    );
+    let Some(new_frametime) = resolved
+        .map(|frametime| match limiter {
+            Limiter::IdleRamp(ramp) => ramp.frametime(frametime, last_input.0.elapsed()),
+            _ => frametime,
+        })
+        .map(|frametime| match speed_scale.0 {
+            // Saturate rather than panic if a huge scale overflows the frametime.
+            scale if scale.is_finite() && scale > 0.0 => {
+                Duration::try_from_secs_f64(frametime.as_secs_f64() * f64::from(scale))
+                    .unwrap_or(Duration::MAX)
+            }
+            _ => frametime,
+        })
+        .map(|frametime| frametime.max(settings.min_frametime))
+    else {
+        #[cfg(feature = "framepace_debug")]
+        if !limiter.is_enabled()
+            && (settings.is_changed() || activity.is_changed() || occlusion.is_changed())
+        {
+            bevy_log::info!("Frame limiter disabled");
+        }
+        return;
+    };
+
+    if let Ok(mut limit) = frame_limit.limit.try_lock() {
+        if new_frametime != *limit {
+            #[cfg(feature = "framepace_debug")]
+            bevy_log::info!("Frametime limit changed to: {:?}", new_frametime);
+            *limit = new_frametime;
+            frame_limit
+                .last
+                .store(new_frametime.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns the frametime of the slowest refresh rate among the windows presenting frames, from
+/// the `(visible, minimized, refresh_millihertz)` of each window.
+///
+/// Hidden or minimized windows aren't presenting, and may report a stale refresh rate. Platforms
+/// that can't tell whether a window is minimized report `None`, in which case the window is
+/// assumed to be presenting.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn presenting_frametime(
+    windows: impl IntoIterator<Item = (bool, Option<bool>, Option<u32>)>,
+    rounding: RefreshRounding,
+) -> Option<Duration> {
+    let slowest = windows
+        .into_iter()
+        .filter(|&(visible, minimized, _)| visible && minimized != Some(true))
+        .filter_map(|(_, _, millihertz)| millihertz)
+        .filter(|&millihertz| millihertz > 0)
+        .min();
+    millihertz_frametime(slowest, rounding)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn millihertz_frametime(millihertz: Option<u32>, rounding: RefreshRounding) -> Option<Duration> {
+    let millihertz = millihertz.filter(|&millihertz| millihertz > 0)?;
+    // Winit may only provide integer refresh rate values, by default we round down to handle the
+    // worst case scenario of a rounded refresh rate.
+    let framerate = rounding.framerate(millihertz);
+    (framerate > 0.0).then(|| Duration::from_secs_f64(1.0 / framerate))
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn detect_frametime<'a>(
+    winit: NonSend<WinitWindows>,
+    windows: impl Iterator<Item = (Entity, &'a Window)>,
+    rounding: RefreshRounding,
+    monitor: Option<&MonitorSelector>,
+) -> Option<Duration> {
+    match monitor {
+        Some(selector) => {
+            millihertz_frametime(selected_monitor_millihertz(&winit, selector), rounding)
+        }
+        None => presenting_frametime(
+            windows.filter_map(|(e, window)| {
+                let w = winit.get_window(e)?;
+                let millihertz = w
+                    .current_monitor()
+                    .and_then(|monitor| monitor.refresh_rate_millihertz());
+                Some((window.visible, w.is_minimized(), millihertz))
+            }),
+            rounding,
+        ),
+    }
+}
+
+/// The worst measured overshoot of the OS sleep, inserted as a resource shortly after the limiter
+/// is first applied. The measurement runs on a background thread.
+///
+/// `spin_sleep` spins for the portion of the sleep the OS can't accurately provide. On platforms
+/// with poor sleep accuracy, such as some virtualized or CI environments, this means most of the
+/// time is spent spinning, burning CPU. Use [`SleepAccuracy::is_poor`] to decide whether to fall
+/// back to a less demanding configuration.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct SleepAccuracy(pub Duration);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SleepAccuracy {
+    /// Sleep overshoot above which the accuracy is considered poor.
+    pub const POOR_THRESHOLD: Duration = Duration::from_millis(4);
+
+    /// Measures the accuracy of the OS sleep by briefly sleeping a few times, blocking for a few
+    /// milliseconds.
+    pub fn measure() -> Self {
+        Self::measure_samples(5)
+    }
+
+    fn measure_samples(samples: usize) -> Self {
+        const REQUESTED: Duration = Duration::from_millis(1);
+        let worst = (0..samples)
+            .map(|_| {
+                let start = Instant::now();
+                std::thread::sleep(REQUESTED);
+                start.elapsed().saturating_sub(REQUESTED)
+            })
+            .max()
+            .unwrap_or_default();
+        SleepAccuracy(worst)
+    }
+
+    /// Returns `true` if the OS sleep overshoot is above [`SleepAccuracy::POOR_THRESHOLD`].
+    pub fn is_poor(&self) -> bool {
+        self.0 > Self::POOR_THRESHOLD
+    }
+}
+
+/// The result of a running sleep accuracy probe.
+#[cfg(not(target_arch = "wasm32"))]
+type ProbeResult = Arc<Mutex<Option<SleepAccuracy>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn probe_sleep_accuracy(
+    mut commands: Commands,
+    effective: Res<EffectiveState>,
+    accuracy: Option<Res<SleepAccuracy>>,
+    mut running: Local<Option<ProbeResult>>,
+) {
+    if accuracy.is_some() || (running.is_none() && !effective.is_limiting()) {
+        return;
+    }
+    // Measuring blocks for a few milliseconds, keep it off the main thread.
+    let result = running.get_or_insert_with(|| {
+        let result = ProbeResult::default();
+        let thread_result = result.clone();
+        std::thread::spawn(move || {
+            let accuracy = SleepAccuracy::measure();
+            if let Ok(mut result) = thread_result.lock() {
+                *result = Some(accuracy);
+            }
+        });
+        result
+    });
+    let Some(accuracy) = result.try_lock().ok().and_then(|result| *result) else {
+        return;
+    };
+    if accuracy.is_poor() {
+        bevy_log::warn!(
+            "Sleep accuracy is poor ({:?} overshoot), frame limiting will spin and use more CPU",
+            accuracy.0
+        );
+    }
+    commands.insert_resource(accuracy);
+}
+
+/// Returns the refresh rates in millihertz supported by the current monitor of `window`, sorted
+/// from slowest to fastest, e.g. to offer valid framerate caps in a settings menu.
+///
+/// Returns an empty list if the window or its monitor can't be found. Native only, requires the
+/// `winit` feature.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+pub fn available_refresh_rates(winit: &WinitWindows, window: Entity) -> Vec<u32> {
+    let Some(monitor) = winit
+        .get_window(window)
+        .and_then(|window| window.current_monitor())
+    else {
+        return Vec::new();
+    };
+    let mut rates: Vec<u32> = monitor
+        .video_modes()
+        .map(|mode| mode.refresh_rate_millihertz())
+        .filter(|&millihertz| millihertz > 0)
+        .collect();
+    rates.sort_unstable();
+    rates.dedup();
+    rates
+}
+
+/// Returns the connected monitors, in the order the OS lists them.
+///
+/// Returns an empty list if there is no window to query the monitors from. Native only, requires
+/// the `winit` feature.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+pub fn available_monitors(winit: &WinitWindows) -> Vec<MonitorInfo> {
+    let Some(window) = winit.windows.values().next() else {
+        return Vec::new();
+    };
+    window
+        .available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| MonitorInfo {
+            index,
+            name: monitor.name(),
+            refresh_rate_millihertz: monitor.refresh_rate_millihertz(),
+        })
+        .collect()
+}
+
+/// Returns the refresh rate in millihertz of the monitor chosen by `selector`, if it is connected.
+#[cfg(all(not(target_arch = "wasm32"), feature = "winit"))]
+fn selected_monitor_millihertz(winit: &WinitWindows, selector: &MonitorSelector) -> Option<u32> {
+    let window = winit.windows.values().next()?;
+    let mut monitors = window.available_monitors();
+    match selector {
+        MonitorSelector::Primary => window.primary_monitor(),
+        MonitorSelector::Index(index) => monitors.nth(*index),
+        MonitorSelector::Name(name) => {
+            monitors.find(|monitor| monitor.name().as_deref() == Some(name.as_str()))
+        }
+    }?
+    .refresh_rate_millihertz()
+}
+
+/// The spin threshold chosen by the sleep calibration, inserted as a resource once the
+/// calibration enabled with [`FramepaceSettings::calibrate`] completes.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct SleepCalibration {
+    /// The duration before each deadline that the limiter spins instead of sleeping.
+    pub spin_threshold: Duration,
+}
+
+/// The result of a running sleep calibration.
+#[cfg(not(target_arch = "wasm32"))]
+type CalibrationResult = Arc<Mutex<Option<Duration>>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn calibrate_sleeper(
+    mut commands: Commands,
+    settings: Res<FramepaceSettings>,
+    proxy: Res<FramepaceSettingsProxy>,
+    calibration: Option<Res<SleepCalibration>>,
+    mut running: Local<Option<CalibrationResult>>,
+) {
+    if !settings.calibrate || calibration.is_some() {
+        return;
+    }
+    let result = running.get_or_insert_with(|| {
+        let result = CalibrationResult::default();
+        let thread_result = result.clone();
+        std::thread::spawn(move || {
+            // Sleep accuracy varies, leave some headroom over the worst measured overshoot.
+            let threshold = SleepAccuracy::measure_samples(50).0.mul_f64(1.2);
+            if let Ok(mut result) = thread_result.lock() {
+                *result = Some(threshold);
+            }
+        });
+        result
+    });
+    let Some(spin_threshold) = result.try_lock().ok().and_then(|result| *result) else {
+        return;
+    };
+    let Ok(mut spin_calibration) = proxy.spin_calibration.try_lock() else {
+        return;
+    };
+    *spin_calibration = Some(spin_threshold);
+    bevy_log::info!("Calibrated sleep spin threshold: {:?}", spin_threshold);
+    commands.insert_resource(SleepCalibration { spin_threshold });
+}
+
+/// Holds frame time measurements for framepacing diagnostics
+///
+/// Use [`FramePaceStats::snapshot`] to read a copy of the latest measurements.
+#[derive(Clone, Debug, Resource)]
+pub struct FramePaceStats {
+    /// Frametime in nanoseconds, excluding the time spent sleeping.
+    frametime: Arc<AtomicU64>,
+    /// Oversleep in nanoseconds.
+    oversleep: Arc<AtomicU64>,
+    /// Undersleep in nanoseconds.
+    undersleep: Arc<AtomicU64>,
+    /// Requested sleep duration in nanoseconds.
+    sleep_time: Arc<AtomicU64>,
+    /// Time between the end of the previous sleep and the limiter, in nanoseconds.
+    work_time: Arc<AtomicU64>,
+    /// When the limiter intends to wake up from the current or last sleep.
+    next_wake: Arc<Mutex<Option<Instant>>>,
+    /// Target frametime in nanoseconds, `0` if the limiter is disabled.
+    target_frametime: Arc<AtomicU64>,
+    /// Shortest frametime in nanoseconds since the last reset, `u64::MAX` if none was recorded.
+    min_frametime: Arc<AtomicU64>,
+    /// Longest frametime in nanoseconds since the last reset.
+    max_frametime: Arc<AtomicU64>,
+    history: Arc<Mutex<VecDeque<FrameRecord>>>,
+    /// The label attached to newly recorded frames.
+    label: Arc<Mutex<Option<Arc<str>>>>,
+    /// A stall detected in the render world, waiting to be sent as an event.
+    stall: Arc<Mutex<Option<u32>>>,
+    /// Smoothed headroom fraction, stored as the bits of an `f64`.
+    headroom_fraction: Arc<AtomicU64>,
+    /// Headroom above the threshold detected in the render world, waiting to be sent as an event.
+    headroom: Arc<Mutex<Option<f64>>>,
+    unable_to_reach_target: Arc<AtomicBool>,
+    /// Whether the applied limiter is waiting for the display refresh rate.
+    awaiting_detection: Arc<AtomicBool>,
+    /// The number of frames measured by the limiter.
+    measured_frames: Arc<AtomicU32>,
+    /// The number of frames processed by the limiter.
+    frame_count: Arc<AtomicU64>,
+    /// The sleep strategy used by the limiter.
+    sleep_strategy: Arc<Mutex<SleepStrategy>>,
+    /// How much longer than requested the last sleep took, in nanoseconds.
+    sleep_overshoot: Arc<AtomicU64>,
+}
+impl Default for FramePaceStats {
+    fn default() -> Self {
+        FramePaceStats {
+            frametime: Default::default(),
+            oversleep: Default::default(),
+            undersleep: Default::default(),
+            sleep_time: Default::default(),
+            work_time: Default::default(),
+            next_wake: Default::default(),
+            target_frametime: Default::default(),
+            min_frametime: Arc::new(AtomicU64::new(u64::MAX)),
+            max_frametime: Default::default(),
+            history: Default::default(),
+            label: Default::default(),
+            stall: Default::default(),
+            headroom_fraction: Default::default(),
+            headroom: Default::default(),
+            unable_to_reach_target: Default::default(),
+            awaiting_detection: Default::default(),
+            measured_frames: Default::default(),
+            frame_count: Default::default(),
+            sleep_strategy: Default::default(),
+            sleep_overshoot: Default::default(),
+        }
+    }
+}
+impl FramePaceStats {
+    /// The number of recent frames considered by [`FramePaceStats::unable_to_reach_target`].
+    pub const DEGRADED_WINDOW: u32 = 60;
+    /// The number of recent frames that must miss the target for the app to be considered unable
+    /// to reach it.
+    pub const DEGRADED_FRAMES: u32 = 30;
+    /// The ratio of the target frametime above which a frame counts as missing the target.
+    pub const DEGRADED_MARGIN: f64 = 1.1;
+    /// The number of frames the [`FramePaceStats::headroom_fraction`] is averaged over.
+    pub const HEADROOM_WINDOW: u32 = 60;
+
+    /// The time spent on the previous frame, excluding the time spent sleeping.
+    pub fn frametime(&self) -> Duration {
+        Duration::from_nanos(self.frametime.load(Ordering::Relaxed))
+    }
+
+    /// How long the previous frame overshot the target frametime.
+    pub fn oversleep(&self) -> Duration {
+        Duration::from_nanos(self.oversleep.load(Ordering::Relaxed))
+    }
+
+    /// The frametime the limiter is currently pacing the app to, or `None` if limiting is disabled.
+    ///
+    /// This is the cadence the app is intended to run at, including adjustments made by
+    /// [`Limiter::Adaptive`]. Bevy's [`Time`](bevy_time::Time) measures the actual frame deltas
+    /// independently, and this crate does not alter them. However, this can be used to make other
+    /// timing decisions match the frame cadence, e.g. by setting the timestep of
+    /// `Time<Fixed>` to a multiple of the target frametime, or when interpolating between fixed
+    /// updates.
+    pub fn target_frametime(&self) -> Option<Duration> {
+        match self.target_frametime.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// The shortest frametime recorded since the last [`FramePaceStats::reset`], if any.
+    pub fn min_frametime(&self) -> Option<Duration> {
+        match self.min_frametime.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// The longest frametime recorded since the last [`FramePaceStats::reset`], if any. This is the
+    /// worst hitch of the session.
+    pub fn max_frametime(&self) -> Option<Duration> {
+        self.min_frametime()?;
+        Some(Duration::from_nanos(
+            self.max_frametime.load(Ordering::Relaxed),
+        ))
+    }
+
+    /// Returns `true` while the app is persistently unable to reach the target frametime.
+    ///
+    /// This becomes `true` when at least [`FramePaceStats::DEGRADED_FRAMES`] of the last
+    /// [`FramePaceStats::DEGRADED_WINDOW`] frames exceeded the target frametime by more than
+    /// [`FramePaceStats::DEGRADED_MARGIN`], and `false` again once the app recovers. This can be
+    /// used to tell the user that performance is degraded.
+    pub fn unable_to_reach_target(&self) -> bool {
+        self.unable_to_reach_target.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` while the applied limiter follows the display, e.g. [`Limiter::Auto`], but the
+    /// display refresh rate has not been detected yet.
+    ///
+    /// The first detection completes a frame or two after startup. Until then, frames are not
+    /// limited rather than paced to a guessed target. This is also `true` while the refresh rate is
+    /// re-detected after [`DetectedFrametime::force_refresh_detection`], in which case the previous
+    /// target is kept. Always `false` on the web.
+    pub fn awaiting_detection(&self) -> bool {
+        self.awaiting_detection.load(Ordering::Relaxed)
+    }
+
+    /// The fraction of the target frametime left unused by the recent frames, `1 - work_time /
+    /// target`, in the range `0.0..=1.0`.
+    ///
+    /// This is an exponential moving average over roughly [`FramePaceStats::HEADROOM_WINDOW`]
+    /// frames, weighting the latest frame by `2 / (HEADROOM_WINDOW + 1)`, so it reflects sustained
+    /// headroom rather than single fast frames. It is the counterpart of
+    /// [`FramePaceStats::unable_to_reach_target`]: a value close to `1.0` means the framerate cap
+    /// could be raised. It is `0.0` while limiting is disabled. See also [`FramepaceHeadroom`].
+    pub fn headroom_fraction(&self) -> f64 {
+        f64::from_bits(self.headroom_fraction.load(Ordering::Relaxed))
+    }
+
+    /// Clears the recorded min and max frametimes, and the [`FramePaceStats::history`].
+    pub fn reset(&self) {
+        self.min_frametime.store(u64::MAX, Ordering::Relaxed);
+        self.max_frametime.store(0, Ordering::Relaxed);
+        if let Ok(mut history) = self.history.try_lock() {
+            history.clear();
+        }
+    }
+
+    /// The number of frames measured by the limiter since startup, i.e. that updated
+    /// [`FramePaceStats::frametime`].
+    ///
+    /// This is at most [`FramePaceStats::frame_count`], which counts every frame reaching the
+    /// limiter. Frames that are not limited are only measured with
+    /// [`FramepaceSettings::update_stats_when_off`], and the first frame after the limiter is
+    /// re-enabled is never measured, as it ran unlimited. Use this count to wait for a number of
+    /// meaningful measurements, e.g. before reading a benchmark result.
+    pub fn measured_frames(&self) -> u32 {
+        self.measured_frames.load(Ordering::Relaxed)
+    }
+
+    fn set_frametime(&self, frametime: Duration) {
+        self.measured_frames.fetch_add(1, Ordering::Relaxed);
+        let nanos = frametime.as_nanos() as u64;
+        self.frametime.store(nanos, Ordering::Relaxed);
+        self.min_frametime.fetch_min(nanos, Ordering::Relaxed);
+        self.max_frametime.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// How much earlier than the target frametime the previous frame ended, i.e. how early the
+    /// limiter woke up. Either this or [`FramePaceStats::oversleep`] is zero.
+    pub fn undersleep(&self) -> Duration {
+        Duration::from_nanos(self.undersleep.load(Ordering::Relaxed))
+    }
+
+    /// The signed error of the previous frame's length relative to the target frametime, in
+    /// seconds. Positive values are an [`FramePaceStats::oversleep`], negative values an
+    /// [`FramePaceStats::undersleep`].
+    pub fn sleep_error_secs(&self) -> f64 {
+        self.oversleep().as_secs_f64() - self.undersleep().as_secs_f64()
+    }
+
+    /// How long the limiter slept at the end of the previous frame.
+    pub fn sleep_time(&self) -> Duration {
+        Duration::from_nanos(self.sleep_time.load(Ordering::Relaxed))
+    }
+
+    /// Copies the latest measurements out of the stats.
+    ///
+    /// This is the recommended way to read the stats: clones of [`FramePaceStats`] share their
+    /// measurements and keep changing as frames are limited, while a snapshot is a plain value
+    /// that can be stored, compared and sent around freely.
+    ///
+    /// Each measurement is read separately, so with pipelined rendering, a snapshot taken while
+    /// the limiter is recording a frame can mix measurements of that frame and the previous one.
+    pub fn snapshot(&self) -> FramePaceSnapshot {
+        FramePaceSnapshot {
+            frametime: self.frametime(),
+            oversleep: self.oversleep(),
+            undersleep: self.undersleep(),
+            work_time: self.work_time(),
+            sleep_time: self.sleep_time(),
+            target_frametime: self.target_frametime(),
+            sleep_strategy: self.sleep_strategy(),
+            sleep_overshoot: self.sleep_overshoot(),
+            frame_count: self.frame_count(),
+            min_frametime: self.min_frametime(),
+            max_frametime: self.max_frametime(),
+            unable_to_reach_target: self.unable_to_reach_target(),
+            headroom_fraction: self.headroom_fraction(),
+        }
+    }
+
+    /// The number of frames processed by the limiter since startup, whether or not they were
+    /// limited.
+    ///
+    /// Unlike bevy's [`FrameCount`], which counts updates of the main app, this counts the frames
+    /// reaching the limiter, once per rendered frame. It only ever increases, e.g. to run logic
+    /// every few hundred paced frames. Unlike [`FramePaceStats::measured_frames`], it also counts
+    /// the frames that were not measured, e.g. while the limiter is off.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count.load(Ordering::Relaxed)
+    }
+
+    /// The [`SleepStrategy`] the limiter currently sleeps with, after applying the
+    /// [`SleepCalibration`] and the [`FramepaceAccuracyPriority`]. Native only, the limiter never
+    /// sleeps on the web.
+    pub fn sleep_strategy(&self) -> SleepStrategy {
+        self.sleep_strategy
+            .try_lock()
+            .as_deref()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// How much longer than requested the limiter's last sleep took, i.e. the accuracy of the
+    /// [`FramePaceStats::sleep_strategy`]. Unlike [`FramePaceStats::oversleep`], this excludes the
+    /// time the frame itself overshot the target.
+    pub fn sleep_overshoot(&self) -> Duration {
+        Duration::from_nanos(self.sleep_overshoot.load(Ordering::Relaxed))
+    }
+
+    /// A one line, human readable summary of the active [`FramePaceStats::sleep_strategy`] and its
+    /// accuracy, e.g. for bug reports about poor pacing or high CPU use.
+    pub fn sleep_strategy_summary(&self) -> String {
+        let strategy = match self.sleep_strategy() {
+            SleepStrategy::Spin => "spin".to_string(),
+            SleepStrategy::NoSpin => "no spin".to_string(),
+            SleepStrategy::SpinThreshold(threshold) => {
+                format!("spin threshold {:.3}ms", threshold.as_secs_f64() * 1_000.0)
+            }
+        };
+        format!(
+            "Sleep strategy: {}, slept {:.3}ms, overshot by {:.3}ms",
+            strategy,
+            self.sleep_time().as_secs_f64() * 1_000.0,
+            self.sleep_overshoot().as_secs_f64() * 1_000.0
+        )
+    }
+
+    /// How long the previous frame spent working, i.e. the whole frame except the limiter sleep.
+    ///
+    /// Unlike [`FramePaceStats::frametime`], this is always measured right before the limiter
+    /// sleeps, regardless of the [`FramepaceSettings::measurement_point`]. Comparing it to the
+    /// [`FramePaceStats::target_frametime`] shows how much headroom the app has.
+    pub fn work_time(&self) -> Duration {
+        Duration::from_nanos(self.work_time.load(Ordering::Relaxed))
+    }
+
+    /// The instant the limiter intends to wake up at the end of the current, or last, sleep, e.g.
+    /// to schedule audio prefetching or network sends right before the next frame starts.
+    ///
+    /// This is updated every frame before the limiter starts sleeping, and is `None` while the
+    /// limiter is disabled. Native only, the limiter never sleeps on the web.
+    pub fn next_wake(&self) -> Option<Instant> {
+        self.next_wake
+            .try_lock()
+            .ok()
+            .and_then(|next_wake| *next_wake)
+    }
+
+    fn set_undersleep(&self, undersleep: Duration) {
+        self.undersleep
+            .store(undersleep.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn set_oversleep(&self, oversleep: Duration) {
+        self.oversleep
+            .store(oversleep.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the recorded `(frame, frametime, oversleep)` measurements, oldest first.
+    ///
+    /// The frame number is bevy's [`FrameCount`], which makes it possible to correlate pacing
+    /// hiccups with what the app was doing on that frame. This is empty unless
+    /// [`FramepaceSettings::history_len`] is set.
+    pub fn history(&self) -> Vec<(u32, Duration, Duration)> {
+        self.history
+            .try_lock()
+            .map(|history| {
+                history
+                    .iter()
+                    .map(|record| (record.frame, record.frametime, record.oversleep))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the recorded history like [`FramePaceStats::history`], including the
+    /// [`FramepaceStatsConfig::current_label`] at the time each frame was recorded.
+    pub fn labeled_history(&self) -> Vec<FrameRecord> {
+        self.history
+            .try_lock()
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn record_history(&self, frame: u32, frametime: Duration, oversleep: Duration, len: usize) {
+        if let Ok(mut history) = self.history.try_lock() {
+            while history.len() >= len.max(1) {
+                history.pop_front();
+            }
+            if len > 0 {
+                let label = self.label.try_lock().ok().and_then(|label| label.clone());
+                history.push_back(FrameRecord {
+                    frame,
+                    frametime,
+                    oversleep,
+                    label,
+                });
+            }
+        }
+    }
+}
+
+/// A copy of the latest [`FramePaceStats`] measurements, see [`FramePaceStats::snapshot`].
+///
+/// The plugin also keeps a reflected copy of this as a resource, updated every frame in the main
+/// world, so tools using reflection, e.g. the bevy remote protocol, can read the stats. Changes to
+/// the [`FramepaceSettings`] resource made through reflection are applied to the limiter like any
+/// other change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct FramePaceSnapshot {
+    /// See [`FramePaceStats::frametime`].
+    pub frametime: Duration,
+    /// See [`FramePaceStats::oversleep`].
+    pub oversleep: Duration,
+    /// See [`FramePaceStats::undersleep`].
+    pub undersleep: Duration,
+    /// See [`FramePaceStats::work_time`].
+    pub work_time: Duration,
+    /// See [`FramePaceStats::sleep_time`].
+    pub sleep_time: Duration,
+    /// See [`FramePaceStats::target_frametime`].
+    pub target_frametime: Option<Duration>,
+    /// See [`FramePaceStats::sleep_strategy`].
+    pub sleep_strategy: SleepStrategy,
+    /// See [`FramePaceStats::sleep_overshoot`].
+    pub sleep_overshoot: Duration,
+    /// See [`FramePaceStats::frame_count`].
+    pub frame_count: u64,
+    /// See [`FramePaceStats::min_frametime`].
+    pub min_frametime: Option<Duration>,
+    /// See [`FramePaceStats::max_frametime`].
+    pub max_frametime: Option<Duration>,
+    /// See [`FramePaceStats::unable_to_reach_target`].
+    pub unable_to_reach_target: bool,
+    /// See [`FramePaceStats::headroom_fraction`].
+    pub headroom_fraction: f64,
+}
+
+/// A single frame recorded in the [`FramePaceStats`] history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameRecord {
+    /// Bevy's [`FrameCount`] of the frame.
+    pub frame: u32,
+    /// The time spent on the frame, excluding the time spent sleeping.
+    pub frametime: Duration,
+    /// How long the frame overshot the target frametime.
+    pub oversleep: Duration,
+    /// The [`FramepaceStatsConfig::current_label`] when the frame was recorded.
+    pub label: Option<Arc<str>>,
+}
+
+/// Configures how [`FramePaceStats`] are recorded.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepaceStatsConfig {
+    /// A label attached to every frame recorded in the history while it is set, e.g. the name of
+    /// the current scene, to segment profiling data.
+    pub current_label: Option<String>,
+}
+
+fn update_stats_snapshot(stats: Res<FramePaceStats>, mut snapshot: ResMut<FramePaceSnapshot>) {
+    snapshot.set_if_neq(stats.snapshot());
+}
+
+fn update_stats_label(config: Res<FramepaceStatsConfig>, stats: Res<FramePaceStats>) {
+    if config.is_changed() {
+        if let Ok(mut label) = stats.label.try_lock() {
+            *label = config.current_label.as_deref().map(Arc::from);
+        }
+    }
+}
+
+/// A handle to feed real present timestamps, e.g. from a swapchain or compositor timing API, back
+/// into the limiter to correct its drift.
+///
+/// By default, the limiter measures each frame from the end of its own previous sleep. When a
+/// timestamp was pushed since the last frame, the limiter measures the frame from that present
+/// instead, so frames are paced relative to when they actually reached the display. Only the
+/// latest timestamp is used, and timestamps in the future are ignored. Without timestamps, the
+/// limiter behaves as usual. Clone the resource to push timestamps from any thread. Native only.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepacePresentFeedback(Arc<Mutex<Option<Instant>>>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FramepacePresentFeedback {
+    /// Reports that the last frame was presented at `presented`.
+    pub fn push(&self, presented: Instant) {
+        let mut latest = self
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *latest = Some(presented);
+    }
+
+    /// Takes the latest timestamp, if one was pushed since the last call.
+    fn take(&self) -> Option<Instant> {
+        self.0.try_lock().ok().and_then(|mut latest| latest.take())
+    }
+}
+
+/// A handle that cuts the current frame limiter sleep short, for apps that need to handle events,
+/// such as network messages, as soon as they arrive even when running at a low framerate.
+///
+/// Waking only has an effect when [`FramepaceSettings::interruptible`] is enabled. If the limiter is
+/// not sleeping when woken, the next sleep is skipped. After waking, the frame timer is reset so the
+/// shortened frame does not affect the pacing of the following frames. Native only.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepaceWakeup(Arc<(Mutex<bool>, std::sync::Condvar)>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FramepaceWakeup {
+    /// The final portion of an interruptible sleep which is spun for accuracy, and can't be
+    /// interrupted.
+    const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+    /// Wakes the frame limiter if it is sleeping, or skips its next sleep otherwise.
+    pub fn wake(&self) {
+        let (woken, condvar) = &*self.0;
+        if let Ok(mut woken) = woken.lock() {
+            *woken = true;
+            condvar.notify_all();
+        }
+    }
+
+    /// Sleeps for `duration` unless woken, returns `true` if the sleep was interrupted.
+    fn sleep(&self, duration: Duration, strategy: SleepStrategy) -> bool {
+        let deadline = Instant::now() + duration;
+        let (woken, condvar) = &*self.0;
+        let Ok(guard) = woken.lock() else {
+            strategy.sleep(duration);
+            return false;
+        };
+        let coarse = duration.saturating_sub(Self::SPIN_MARGIN);
+        let mut guard = match condvar.wait_timeout_while(guard, coarse, |woken| !*woken) {
+            Ok((guard, _)) => guard,
+            Err(poisoned) => poisoned.into_inner().0,
+        };
+        if std::mem::take(&mut *guard) {
+            return true;
+        }
+        drop(guard);
+        strategy.sleep(deadline.saturating_duration_since(Instant::now()));
+        false
+    }
+}
+
+/// Returns the time from `now` until the next instant `phase + n * period`.
+#[cfg(not(target_arch = "wasm32"))]
+fn time_until_phase(phase: Instant, period: Duration, now: Instant) -> Duration {
+    let period = period.as_nanos().max(1);
+    let nanos = if phase > now {
+        (phase - now).as_nanos() % period
+    } else {
+        period - (now - phase).as_nanos() % period
+    };
+    Duration::from_nanos(nanos as u64)
+}
+
+#[cfg(feature = "render")]
+fn timestamp_present(mut timer: ResMut<FrameTimer>) {
+    timer.presented = Some(Instant::now());
+}
+
+/// Returns the last boundary of a schedule of `period`s starting at `start`, at or before
+/// `start + elapsed`. Falls back to `start + elapsed` if the period is zero or the boundary
+/// overflows.
+fn last_boundary(start: Instant, elapsed: Duration, period: Duration) -> Instant {
+    elapsed
+        .as_nanos()
+        .checked_div(period.as_nanos())
+        .and_then(|periods| u32::try_from(periods).ok())
+        .and_then(|periods| period.checked_mul(periods))
+        .and_then(|offset| start.checked_add(offset))
+        .unwrap_or(start + elapsed)
+}
+
+/// Accurately sleeps until it's time to start the next frame.
+///
+/// The `spin_sleep` dependency makes it possible to get extremely accurate sleep times across
+/// platforms. Using `std::thread::sleep()` will not be precise enough, especially windows. Using a
+/// spin lock, even with `std::hint::spin_loop()`, will result in significant power usage.
+///
+/// `spin_sleep` sleeps as long as possible given the platform's sleep accuracy, and spins for the
+/// remainder. The dependency is however not WASM compatible, which is fine, because frame limiting
+/// should not be used in a browser; this would compete with the browser's frame limiter.
+#[allow(unused_variables, clippy::too_many_arguments)]
+fn framerate_limiter(
+    mut timer: ResMut<FrameTimer>,
+    mut adaptive: ResMut<AdaptiveState>,
+    mut consistent: ResMut<ConsistentState>,
+    target_frametime: Res<FrametimeLimit>,
+    stats: Res<FramePaceStats>,
+    settings: Res<FramepaceSettingsProxy>,
+    frame_count: Option<Res<FrameCount>>,
+    #[cfg(not(target_arch = "wasm32"))] wakeup: Res<FramepaceWakeup>,
+    #[cfg(not(target_arch = "wasm32"))] present_feedback: Res<FramepacePresentFeedback>,
+) {
+    // The timer and stats must advance exactly once per frame. If the render schedule runs more
+    // than once for the same extracted frame, limiting again would halve the framerate.
+    let frame = frame_count.map(|count| count.0);
+    if frame.is_some() && frame == timer.last_frame {
+        return;
+    }
+    timer.last_frame = frame;
+    stats.frame_count.fetch_add(1, Ordering::Relaxed);
+
+    if let Ok(limit) = target_frametime.limit.try_lock() {
+        let config = settings.config();
+        // A timer ending in the future, e.g. after a clock adjustment, can't measure the frame.
+        // Restart it so the frame is measured as zero instead of a bogus duration.
+        let now = Instant::now();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(presented) = present_feedback
+            .take()
+            .filter(|presented| *presented <= now)
+        {
+            timer.sleep_end = presented;
+        }
+        if timer.sleep_end > now {
+            timer.sleep_end = now;
+        }
+        let frame_time = now.saturating_duration_since(timer.sleep_end);
+        #[cfg(not(target_arch = "wasm32"))]
+        let frame_start = timer.sleep_end;
+        let presented = timer.presented.take();
+        let measured_time = match config.measurement_point {
+            MeasurementPoint::Cleanup => frame_time,
+            MeasurementPoint::Present => presented
+                .map(|presented| presented.saturating_duration_since(timer.sleep_end))
+                .unwrap_or(frame_time),
+        };
+        let limit = match settings.adaptive() {
+            Some(adaptive_settings) => adaptive.update(&adaptive_settings, *limit, frame_time),
+            None => {
+                *adaptive = AdaptiveState::default();
+                *limit
+            }
+        };
+        let limit = match settings.consistent() {
+            Some(consistent_settings) => consistent.update(&consistent_settings, limit, frame_time),
+            None => {
+                *consistent = ConsistentState::default();
+                limit
+            }
+        };
+        let limit = match config.max_cpu_fraction() {
+            Some(fraction) if !limit.is_zero() => limit.max(frame_time.div_f64(fraction)),
+            _ => limit,
+        };
+        let startup = timer.frames < config.fast_startup_frames;
+        timer.frames = timer.frames.saturating_add(1);
+        if startup || settings.take_step_once() {
+            timer.sleep_end = Instant::now();
+            stats
+                .work_time
+                .store(frame_time.as_nanos() as u64, Ordering::Relaxed);
+            stats.set_frametime(measured_time);
+            stats.set_oversleep(Duration::ZERO);
+            stats.set_undersleep(Duration::ZERO);
+            return;
+        }
+
+        // A zero target frametime can't be limited to, treat it as unlimited rather than recording
+        // the whole frame as oversleep.
+        let unlimited = limit.is_zero();
+        // Before the refresh rate is first detected there is no target yet, which is expected.
+        if unlimited
+            && settings.is_enabled()
+            && !stats.awaiting_detection()
+            && !target_frametime.unlimited.load(Ordering::Relaxed)
+            && !timer.warned_zero_limit
+        {
+            bevy_log::warn!("Target frametime is zero, frames will not be limited");
+            timer.warned_zero_limit = true;
+        }
+        let enabled = settings.is_enabled() && !unlimited;
+        let resumed = enabled && !timer.enabled;
+        timer.enabled = enabled;
+        let target = if enabled { limit } else { Duration::ZERO };
+        stats
+            .target_frametime
+            .store(target.as_nanos() as u64, Ordering::Relaxed);
+        if !enabled && !config.update_stats_when_off {
+            timer.sleep_end = Instant::now();
+            return;
+        }
+        stats
+            .work_time
+            .store(frame_time.as_nanos() as u64, Ordering::Relaxed);
+        // The first frame after the limiter is re-enabled was run unlimited, recording it would
+        // skew the stats and the oversleep compensation of the following frames.
+        if resumed {
+            timer.sleep_end = Instant::now();
+            stats.set_oversleep(Duration::ZERO);
+            stats.set_undersleep(Duration::ZERO);
+            return;
+        }
+        if let Some(hook) = settings.budget_hook().filter(|_| enabled) {
+            (hook.0)(frame_time, limit, limit.saturating_sub(frame_time));
+        }
+        let late = enabled && config.drop_late_frames && frame_time > limit;
+        let realigned = std::mem::replace(&mut timer.realigned, late);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let sleep_start = Instant::now();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Limit the overshoot of a hitch before smoothing, so it doesn't linger in the average.
+            // The lateness of a realigned frame was already dropped, and is not compensated.
+            let oversleep = match config.hitch_compensation_limit {
+                _ if realigned => Duration::ZERO,
+                Some(limit) => stats.oversleep().min(limit),
+                None => stats.oversleep(),
+            };
+            let oversleep = match config.oversleep_smoothing() {
+                Some(smoothing) => {
+                    let smoothed = timer.smoothed_oversleep.as_secs_f64();
+                    let oversleep = oversleep.as_secs_f64();
+                    timer.smoothed_oversleep =
+                        Duration::from_secs_f64(smoothed + (oversleep - smoothed) * smoothing);
+                    timer.smoothed_oversleep
+                }
+                None => oversleep,
+            };
+            let input = PacingInput {
+                frame_time,
+                target: limit,
+                oversleep,
+                stats: &stats,
+            };
+            let sleep_time = match (settings.phase(), settings.pacing()) {
+                (Some(phase), _) => time_until_phase(phase, limit, Instant::now()),
+                (None, Some(pacing)) => match pacing.0.try_lock() {
+                    Ok(mut pacing) => pacing.sleep_time(&input),
+                    Err(_) => DefaultPacing.sleep_time(&input),
+                },
+                (None, None) => DefaultPacing.sleep_time(&input),
+            }
+            .max(limit.mul_f64(config.reserve_fraction));
+            let sleep_time = match settings.sleep_hook() {
+                Some(hook) => (hook.0)(sleep_time, &stats),
+                None => sleep_time,
+            };
+            let sleep_time = match config.max_frame_sleep() {
+                Some(max) if sleep_time > max => {
+                    if enabled && !timer.warned_sleep_clamp {
+                        bevy_log::warn!(
+                            "Limiter sleep of {:?} clamped to the maximum frame sleep of {:?}",
+                            sleep_time,
+                            max
+                        );
+                        timer.warned_sleep_clamp = true;
+                    }
+                    max
+                }
+                _ => sleep_time,
+            };
+            // A late frame is dropped from the schedule rather than delayed further.
+            let sleep_time = if late { Duration::ZERO } else { sleep_time };
+            let slept = if enabled { sleep_time } else { Duration::ZERO };
+            stats
+                .sleep_time
+                .store(slept.as_nanos() as u64, Ordering::Relaxed);
+            if let Ok(mut next_wake) = stats.next_wake.try_lock() {
+                *next_wake = enabled.then(|| Instant::now() + sleep_time);
+            }
+            let strategy = config.sleep_strategy(settings.spin_calibration());
+            if let Ok(mut active) = stats.sleep_strategy.try_lock() {
+                *active = strategy;
+            }
+            let sleep_started = Instant::now();
+            if enabled && config.interruptible {
+                if wakeup.sleep(sleep_time, strategy) {
+                    timer.sleep_end = Instant::now();
+                    stats.set_frametime(measured_time);
+                    stats.set_oversleep(Duration::ZERO);
+                    stats.set_undersleep(Duration::ZERO);
+                    return;
+                }
+            } else if enabled {
+                strategy.sleep(sleep_time);
+            }
+            let overshoot = sleep_started.elapsed().saturating_sub(slept);
+            stats
+                .sleep_overshoot
+                .store(overshoot.as_nanos() as u64, Ordering::Relaxed);
+            if let Some(phase) = settings.shared_phase().filter(|_| enabled) {
+                phase.set_if_unset(Instant::now());
+            }
+        }
+
+        if enabled && frame_time > limit {
+            timer.missed_frames += 1;
+        } else {
+            timer.missed_frames = 0;
+        }
+        let degraded = enabled && frame_time > limit.mul_f64(FramePaceStats::DEGRADED_MARGIN);
+        timer.recent_misses = ((timer.recent_misses << 1) | degraded as u64)
+            & ((1 << FramePaceStats::DEGRADED_WINDOW) - 1);
+        stats.unable_to_reach_target.store(
+            timer.recent_misses.count_ones() >= FramePaceStats::DEGRADED_FRAMES,
+            Ordering::Relaxed,
+        );
+
+        let headroom = if enabled {
+            let smoothing = 2.0 / (FramePaceStats::HEADROOM_WINDOW as f64 + 1.0);
+            let latest = (1.0 - frame_time.as_secs_f64() / limit.as_secs_f64()).clamp(0.0, 1.0);
+            let smoothed = stats.headroom_fraction();
+            smoothed + (latest - smoothed) * smoothing
+        } else {
+            0.0
+        };
+        stats
+            .headroom_fraction
+            .store(headroom.to_bits(), Ordering::Relaxed);
+        let exceeded = config
+            .headroom_threshold()
+            .is_some_and(|threshold| headroom > threshold);
+        if exceeded && !timer.headroom_exceeded {
+            if let Ok(mut event) = stats.headroom.try_lock() {
+                *event = Some(headroom);
+            }
+        }
+        timer.headroom_exceeded = exceeded;
+
+        let stall_threshold = config.stall_threshold;
+        if stall_threshold > 0 && timer.missed_frames == stall_threshold {
+            bevy_log::error!(
+                "Missed the target frametime of {:?} for {} consecutive frames",
+                limit,
+                timer.missed_frames
+            );
+            if let Ok(mut stall) = stats.stall.try_lock() {
+                *stall = Some(timer.missed_frames);
+            }
+        }
+
+        let now = Instant::now();
+        let frame_time_total = now.saturating_duration_since(timer.sleep_end);
+        timer.sleep_end = if late {
+            // Measure the next frame from the last boundary of the schedule, dropping the lateness.
+            last_boundary(timer.sleep_end, frame_time_total, limit)
+        } else {
+            now
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeline) = settings.timeline() {
+            timeline.record(TimelineFrame {
+                frame_start,
+                sleep_start,
+                sleep_end: now,
+            });
+        }
+        #[cfg(all(feature = "framepace_debug", not(target_arch = "wasm32")))]
+        if enabled {
+            let slept = frame_time_total.saturating_sub(frame_time);
+            timer
+                .sleep_error
+                .record(stats.sleep_time(), slept, frame_time, limit);
+            timer.conflict.record(frame_time, limit);
+        }
+        let (oversleep, undersleep) = if unlimited {
+            (Duration::ZERO, Duration::ZERO)
+        } else {
+            (
+                frame_time_total.saturating_sub(limit),
+                limit.saturating_sub(frame_time_total),
+            )
+        };
+        stats.set_frametime(measured_time);
+        stats.set_oversleep(oversleep);
+        stats.set_undersleep(undersleep);
+        stats.record_history(
+            frame.unwrap_or_default(),
+            measured_time,
+            oversleep,
+            config.history_len,
+        );
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::RunSystemOnce;
+
+    #[test]
+    fn adaptive_never_steps_below_the_refresh_rate() {
+        let settings = AdaptiveSettings {
+            consecutive_frames: 0,
+            cooldown: Duration::ZERO,
+            max_divisor: 1,
+            ..Default::default()
+        };
+        let mut state = AdaptiveState::default();
+        let refresh = Duration::from_millis(16);
+        for _ in 0..3 {
+            assert_eq!(state.update(&settings, refresh, Duration::ZERO), refresh);
+            assert_eq!(state.divisor, 1);
+        }
+        // Before the refresh rate is detected, the refresh frametime is zero.
+        for _ in 0..3 {
+            assert_eq!(
+                state.update(&settings, Duration::ZERO, Duration::ZERO),
+                Duration::ZERO
+            );
+            assert_eq!(state.divisor, 1);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn sleep_accuracy_is_probed_once_the_limiter_is_applied() {
+        let mut app = App::new();
+        app.insert_resource(EffectiveState {
+            configured: Limiter::Auto,
+            applied: Limiter::Off,
+            reason: LimiterReason::Occluded,
+        })
+        .add_systems(Update, probe_sleep_accuracy);
+        app.update();
+        std::thread::sleep(Duration::from_millis(50));
+        app.update();
+        assert!(app.world().get_resource::<SleepAccuracy>().is_none());
+
+        app.world_mut().resource_mut::<EffectiveState>().applied = Limiter::Auto;
+        let start = Instant::now();
+        while app.world().get_resource::<SleepAccuracy>().is_none() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            app.update();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn limiter_parses_each_accepted_form() {
+        let parse = |s: &str| s.parse::<Limiter>();
+        assert_eq!(parse("auto"), Ok(Limiter::Auto));
+        assert_eq!(parse(" AUTO "), Ok(Limiter::Auto));
+        assert_eq!(parse("Off"), Ok(Limiter::Off));
+        assert_eq!(parse("50"), Ok(Limiter::Manual(Duration::from_millis(20))));
+        assert_eq!(
+            parse("50fps"),
+            Ok(Limiter::Manual(Duration::from_millis(20)))
+        );
+        assert_eq!(
+            parse("50 FPS"),
+            Ok(Limiter::Manual(Duration::from_millis(20)))
+        );
+        assert_eq!(
+            parse("12.5ms"),
+            Ok(Limiter::Manual(Duration::from_micros(12_500)))
+        );
+        assert_eq!(
+            parse("20 ms"),
+            Ok(Limiter::Manual(Duration::from_millis(20)))
+        );
+    }
+
+    #[test]
+    fn limiter_rejects_malformed_input() {
+        for input in [
+            "", "fps", "ms", "fast", "-60", "0", "0ms", "-5ms", "nan", "inf", "infms", "1e-300",
+            "1e30ms", "1e300", "1e308ms", "60 hz",
+        ] {
+            assert_eq!(
+                input.parse::<Limiter>(),
+                Err(ParseLimiterError(input.to_string())),
+                "{input:?}"
+            );
+        }
+    }
+
+    /// An app with the [`FramepacePlugin`], see [`test_app`].
+    fn framepace_app() -> App {
+        let mut app = test_app();
+        app.add_plugins(FramepacePlugin);
+        app
+    }
+
+    /// An app with the window and input events read by the [`FramepacePlugin`], and an empty
+    /// render sub-app standing in for the renderer.
+    fn test_app() -> App {
+        let mut app = App::new();
+        #[cfg(feature = "render")]
+        {
+            use bevy_ecs::schedule::ScheduleLabel;
+            let mut render_app = bevy_app::SubApp::new();
+            render_app.add_schedule(bevy_render::Render::base_schedule());
+            render_app.update_schedule = Some(bevy_render::Render.intern());
+            app.insert_sub_app(bevy_render::RenderApp, render_app);
+        }
+        app.add_plugins((
+            bevy_window::WindowPlugin {
+                primary_window: None,
+                exit_condition: bevy_window::ExitCondition::DontExit,
+                ..Default::default()
+            },
+            bevy_input::InputPlugin,
+        ));
+        #[cfg(feature = "winit")]
+        app.insert_non_send_resource(WinitWindows::default());
+        app
+    }
+
+    /// A world with the resources read by [`framerate_limiter`], applying `limiter` with the
+    /// `limit` target frametime.
+    fn limiter_world(limiter: Limiter, limit: Duration) -> World {
+        let mut world = World::new();
+        let proxy = FramepaceSettingsProxy::default();
+        *proxy.limiter.lock().unwrap() = limiter;
+        let frame_limit = FrametimeLimit::default();
+        *frame_limit.limit.lock().unwrap() = limit;
+        world.insert_resource(proxy);
+        world.insert_resource(frame_limit);
+        world.init_resource::<FrameTimer>();
+        world.init_resource::<AdaptiveState>();
+        world.init_resource::<ConsistentState>();
+        world.init_resource::<FramePaceStats>();
+        #[cfg(not(target_arch = "wasm32"))]
+        world.init_resource::<FramepaceWakeup>();
+        #[cfg(not(target_arch = "wasm32"))]
+        world.init_resource::<FramepacePresentFeedback>();
+        world
+    }
+
+    /// A world with the resources read by [`get_display_refresh_rate`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn refresh_rate_world(limiter: Limiter, detected: Option<Duration>) -> World {
+        let mut world = World::new();
+        init_refresh_rate_resources(&mut world, limiter, detected);
+        world
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn init_refresh_rate_resources(
+        world: &mut World,
+        limiter: Limiter,
+        detected: Option<Duration>,
+    ) {
+        world.insert_resource(FramepaceSettings {
+            limiter,
+            ..Default::default()
+        });
+        world.insert_resource(DetectedFrametime(detected));
+        world.init_resource::<FramepaceActivity>();
+        world.init_resource::<WindowOcclusion>();
+        world.init_resource::<FramepaceSpeedScale>();
+        world.init_resource::<FrametimeLimit>();
+        world.init_resource::<LastInput>();
+        world.init_resource::<FramePaceStats>();
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn huge_speed_scale_saturates_the_limit() {
+        let mut world = refresh_rate_world(Limiter::from_framerate(60.0), None);
+        world.insert_resource(FramepaceSpeedScale(f32::MAX));
+        world.run_system_once(get_display_refresh_rate).unwrap();
+        assert_eq!(world.resource::<FrametimeLimit>().current(), Duration::MAX);
+    }
+
+    #[test]
+    fn idle_ramp_interpolates_the_framerate() {
+        let ramp = IdleRampSettings {
+            delay: Duration::from_secs(1),
+            decay: Duration::from_secs(2),
+            floor: Duration::from_millis(100),
+            exponent: 1.0,
+        };
+        let active = Duration::from_millis(10);
+        assert_eq!(ramp.frametime(active, Duration::ZERO), active);
+        assert_eq!(ramp.frametime(active, Duration::from_secs(1)), active);
+        // Halfway between 100 and 10 fps.
+        let halfway = ramp.frametime(active, Duration::from_secs(2));
+        assert!(halfway.abs_diff(Duration::from_secs_f64(1.0 / 55.0)) < Duration::from_micros(1));
+        assert_eq!(ramp.frametime(active, Duration::from_secs(3)), ramp.floor);
+        assert_eq!(ramp.frametime(active, Duration::from_secs(60)), ramp.floor);
+    }
+
+    #[test]
+    fn idle_ramp_handles_degenerate_settings() {
+        let active = Duration::from_millis(10);
+        let idle = [0, 1, 2, 3, 60].map(Duration::from_secs);
+        for floor in [Duration::ZERO, Duration::from_millis(5), Duration::MAX] {
+            for exponent in [0.0, -1.0, f32::NAN, f32::INFINITY, f32::MIN_POSITIVE] {
+                let ramp = IdleRampSettings {
+                    floor,
+                    exponent,
+                    ..Default::default()
+                };
+                for idle in idle {
+                    let frametime = ramp.frametime(active, idle);
+                    assert!(frametime >= active, "{ramp:?} {idle:?}");
+                    assert!(frametime <= floor.max(active), "{ramp:?} {idle:?}");
+                }
+            }
+        }
+        let ramp = IdleRampSettings::default();
+        for idle in idle {
+            assert_eq!(ramp.frametime(Duration::ZERO, idle), Duration::ZERO);
+        }
+        let ramp = IdleRampSettings {
+            floor: Duration::MAX,
+            ..Default::default()
+        };
+        assert_eq!(
+            ramp.frametime(active, Duration::from_secs(60)),
+            Duration::MAX
+        );
+    }
+
+    #[test]
+    fn holding_momentary_uncap_only_changes_the_settings_once() {
+        #[derive(Resource, Default)]
+        struct Changed(Vec<bool>);
+        fn hold(mut settings: ResMut<FramepaceSettings>) {
+            FramepaceSettings::momentary_uncap(&mut settings, true);
+        }
+        fn record(settings: Res<FramepaceSettings>, mut changed: ResMut<Changed>) {
+            changed.0.push(settings.is_changed());
+        }
+        let mut app = framepace_app();
+        app.init_resource::<Changed>().add_systems(
+            Update,
+            (hold, record).chain().before(update_proxy_resources),
+        );
+        for _ in 0..4 {
+            app.update();
+        }
+        assert!(app.world().resource::<FramepaceSettings>().is_uncapped());
+        assert_eq!(
+            app.world().resource::<EffectiveState>().reason,
+            LimiterReason::Uncapped
+        );
+        assert_eq!(
+            app.world().resource::<Changed>().0,
+            [true, false, false, false]
+        );
+    }
+
+    #[cfg(feature = "winit")]
+    #[test]
+    fn refresh_rate_plugin_defers_to_framepace_in_either_order() {
+        for refresh_first in [true, false] {
+            let mut app = test_app();
+            if refresh_first {
+                app.add_plugins((RefreshRatePlugin, FramepacePlugin));
+            } else {
+                app.add_plugins((FramepacePlugin, RefreshRatePlugin));
+            }
+            app.insert_resource(
+                FramepaceSettings::default()
+                    .with_limiter(Limiter::Manual(Duration::from_millis(10))),
+            );
+            app.update();
+            let world = app.world_mut();
+            let standalone = world
+                .run_system_once(not(resource_exists::<FramepaceSettings>))
+                .unwrap();
+            assert!(!standalone, "refresh_first: {refresh_first}");
+            let framepace = world.run_system_once(detects_refresh_rate).unwrap();
+            assert!(framepace, "refresh_first: {refresh_first}");
+        }
+
+        let mut app = framepace_app();
+        app.insert_resource(
+            FramepaceSettings::default().with_limiter(Limiter::Manual(Duration::from_millis(10))),
+        );
+        app.update();
+        let framepace = app
+            .world_mut()
+            .run_system_once(detects_refresh_rate)
+            .unwrap();
+        assert!(!framepace);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn custom_limiter_without_resource_is_unlimited() {
+        let mut world = refresh_rate_world(Limiter::Custom, None);
+        world.run_system_once(get_display_refresh_rate).unwrap();
+        let frame_limit = world.resource::<FrametimeLimit>().clone();
+        let stats = world.resource::<FramePaceStats>().clone();
+        assert_eq!(frame_limit.current(), Duration::ZERO);
+        assert!(!stats.awaiting_detection());
+
+        let mut limiter = limiter_world(Limiter::Custom, Duration::ZERO);
+        limiter.insert_resource(frame_limit.clone());
+        limiter.insert_resource(stats);
+        limiter.run_system_once(framerate_limiter).unwrap();
+        assert!(!limiter.resource::<FrameTimer>().warned_zero_limit);
+
+        world.insert_resource(FramepaceCustomLimiter::new(|| Duration::from_millis(5)));
+        world.run_system_once(get_display_refresh_rate).unwrap();
+        assert_eq!(frame_limit.current(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn snapshot_copies_the_session_extremes() {
+        let mut world = limiter_world(Limiter::from_framerate(1000.0), Duration::from_millis(1));
+        world.resource_mut::<FrameTimer>().sleep_end = Instant::now() - Duration::from_millis(3);
+        world.run_system_once(framerate_limiter).unwrap();
+        let stats = world.resource::<FramePaceStats>();
+        let snapshot = stats.snapshot();
+        assert!(snapshot.frametime >= Duration::from_millis(3));
+        assert_eq!(snapshot.min_frametime, stats.min_frametime());
+        assert_eq!(snapshot.max_frametime, stats.max_frametime());
+        assert!(snapshot.min_frametime.is_some());
+        assert_eq!(snapshot.headroom_fraction, stats.headroom_fraction());
+        assert_eq!(
+            snapshot.unable_to_reach_target,
+            stats.unable_to_reach_target()
+        );
+    }
+
+    #[test]
+    fn unmeasured_frames_only_advance_the_frame_count() {
+        let mut world = limiter_world(Limiter::Off, Duration::from_millis(1));
+        world
+            .resource::<FramepaceSettingsProxy>()
+            .set_config(ProxyConfig {
+                update_stats_when_off: false,
+                ..Default::default()
+            });
+        for _ in 0..3 {
+            world.run_system_once(framerate_limiter).unwrap();
+        }
+        let stats = world.resource::<FramePaceStats>();
+        assert_eq!(stats.frame_count(), 3);
+        assert_eq!(stats.measured_frames(), 0);
+    }
+
+    #[cfg(feature = "winit")]
+    fn detect(
+        In(frametime): In<Option<Duration>>,
+        mut detected: ResMut<DetectedFrametime>,
+        mut refresh_changed: EventWriter<RefreshRateChanged>,
+        mut lost: Local<bool>,
+    ) {
+        update_detected_frametime(frametime, &mut detected, &mut lost, &mut refresh_changed);
+    }
+
+    #[cfg(feature = "winit")]
+    #[test]
+    fn redetecting_the_same_refresh_rate_is_not_a_change() {
+        use bevy_window::WindowScaleFactorChanged;
+        /// The refresh rate a headless window would report, as there is no winit window to query.
+        #[derive(Resource)]
+        struct Display(Option<Duration>);
+        #[derive(Resource, Default)]
+        struct Observed(Vec<(bool, usize)>);
+        fn observe(
+            detected: Res<DetectedFrametime>,
+            mut refresh_changed: EventReader<RefreshRateChanged>,
+            mut observed: ResMut<Observed>,
+        ) {
+            let events = refresh_changed.read().count();
+            observed.0.push((detected.is_changed(), events));
+        }
+        let hz_60 = Duration::from_secs_f64(1.0 / 60.0);
+        let hz_144 = Duration::from_secs_f64(1.0 / 144.0);
+        let mut app = framepace_app();
+        app.insert_resource(Display(Some(hz_60)))
+            .init_resource::<Observed>()
+            .add_systems(
+                Update,
+                ((|display: Res<Display>| display.0).pipe(detect), observe)
+                    .chain()
+                    .before(get_display_refresh_rate),
+            );
+        let run = |app: &mut App| {
+            app.update();
+            let observed = app.world_mut().resource_mut::<Observed>().0.pop();
+            let limit = app.world().resource::<FrametimeLimit>().current();
+            (observed.unwrap(), limit)
+        };
+        assert_eq!(run(&mut app), ((true, 1), hz_60));
+        assert_eq!(run(&mut app), ((false, 0), hz_60));
+        // Changing the fractional display scaling doesn't change the refresh rate.
+        let window = app.world_mut().spawn_empty().id();
+        app.world_mut().send_event(WindowScaleFactorChanged {
+            window,
+            scale_factor: 1.5,
+        });
+        assert_eq!(run(&mut app), ((false, 0), hz_60));
+        assert_eq!(run(&mut app), ((false, 0), hz_60));
+        // Moving to another monitor, or changing its refresh rate.
+        app.world_mut().resource_mut::<Display>().0 = Some(hz_144);
+        assert_eq!(run(&mut app), ((true, 1), hz_144));
+        assert_eq!(run(&mut app), ((false, 0), hz_144));
+    }
+
+    #[test]
+    fn limiter_advances_once_per_frame_count() {
+        let mut world = limiter_world(Limiter::from_framerate(1000.0), Duration::from_millis(1));
+        world.insert_resource(FrameCount(5));
+        world.run_system_once(framerate_limiter).unwrap();
+        world.run_system_once(framerate_limiter).unwrap();
+        assert_eq!(world.resource::<FramePaceStats>().frame_count(), 1);
+        world.insert_resource(FrameCount(6));
+        world.run_system_once(framerate_limiter).unwrap();
+        assert_eq!(world.resource::<FramePaceStats>().frame_count(), 2);
+    }
+
+    /// Returns how long `frames` updates take once the [`Limiter::Manual`] `target` is applied.
+    fn paced_updates(mut app: App, target: Duration, frames: u32) -> Duration {
+        app.finish();
+        app.cleanup();
+        app.world_mut().resource_mut::<FramepaceSettings>().limiter = Limiter::Manual(target);
+        // Let the limit propagate to the limiter and the startup frames pass.
+        for _ in 0..5 {
+            app.update();
+        }
+        let start = Instant::now();
+        for _ in 0..frames {
+            app.update();
+        }
+        start.elapsed()
+    }
+
+    #[test]
+    fn app_updates_are_paced_to_the_target() {
+        let target = Duration::from_millis(10);
+        let elapsed = paced_updates(framepace_app(), target, 10);
+        assert!(elapsed >= target * 9, "{elapsed:?}");
+        assert!(elapsed < target * 50, "{elapsed:?}");
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn pipelined_app_updates_are_paced_to_the_target() {
+        let target = Duration::from_millis(10);
+        let mut app = test_app();
+        app.add_plugins((
+            bevy_core::TaskPoolPlugin::default(),
+            bevy_render::pipelined_rendering::PipelinedRenderingPlugin,
+            FramepacePlugin,
+        ));
+        let elapsed = paced_updates(app, target, 10);
+        assert!(elapsed >= target * 9, "{elapsed:?}");
+        assert!(elapsed < target * 50, "{elapsed:?}");
+    }
+
+    #[cfg(feature = "winit")]
+    #[test]
+    fn only_presenting_windows_are_detected() {
+        let rounding = RefreshRounding::Exact;
+        let hz = |hz: u32| Some(hz * 1000);
+        let windows = [
+            (true, Some(false), hz(144)),
+            (true, None, hz(60)),
+            // The minimized window is on the fastest display, and a hidden one on the slowest.
+            (true, Some(true), hz(240)),
+            (false, Some(false), hz(30)),
+            (true, Some(false), None),
+        ];
+        assert_eq!(
+            presenting_frametime(windows, rounding),
+            Some(Duration::from_secs_f64(1.0 / 60.0))
+        );
+        assert_eq!(
+            presenting_frametime([(true, Some(true), hz(240))], rounding),
+            None
+        );
+        assert_eq!(
+            presenting_frametime([(false, None, hz(30)), (true, None, hz(240))], rounding),
+            Some(Duration::from_secs_f64(1.0 / 240.0))
+        );
+    }
+
+    #[test]
+    fn resumed_frame_is_not_recorded() {
+        let limit = Duration::from_millis(1);
+        let mut world = limiter_world(Limiter::Manual(limit), limit);
+        {
+            let mut timer = world.resource_mut::<FrameTimer>();
+            timer.enabled = false;
+            timer.sleep_end = Instant::now() - Duration::from_millis(50);
+        }
+        world.run_system_once(framerate_limiter).unwrap();
+        let stats = world.resource::<FramePaceStats>().clone();
+        assert_eq!(stats.measured_frames(), 0);
+        assert_eq!(stats.frametime(), Duration::ZERO);
+        assert_eq!(stats.oversleep(), Duration::ZERO);
+        assert!(stats.history().is_empty());
+
+        world.run_system_once(framerate_limiter).unwrap();
+        assert_eq!(stats.measured_frames(), 1);
+        assert!(stats.frametime() < Duration::from_millis(50));
+    }
+
+    #[test]
+    fn limiter_resolves_every_variant() {
+        let ms = Duration::from_millis;
+        let detected = Some(ms(7));
+        for (limiter, with_detection, without_detection) in [
+            (Limiter::Auto, Some(ms(7)), None),
+            (Limiter::AutoCapped(ms(10)), Some(ms(10)), Some(ms(10))),
+            (Limiter::AutoCapped(ms(5)), Some(ms(7)), Some(ms(5))),
+            (Limiter::Manual(ms(20)), Some(ms(20)), Some(ms(20))),
+            (Limiter::Adaptive(Default::default()), Some(ms(7)), None),
+            (Limiter::IdleRamp(Default::default()), Some(ms(7)), None),
+            (Limiter::Consistent(Default::default()), Some(ms(7)), None),
+            (
+                Limiter::AudioLocked {
+                    block: ms(2),
+                    multiple: 8,
+                    phase: None,
+                },
+                Some(ms(16)),
+                Some(ms(16)),
+            ),
+            (Limiter::Custom, None, None),
+            (Limiter::Off, None, None),
+        ] {
+            assert_eq!(limiter.resolve(detected), with_detection, "{limiter:?}");
+            assert_eq!(limiter.resolve(None), without_detection, "{limiter:?}");
+        }
+    }
+
+    #[cfg(feature = "winit")]
+    #[test]
+    fn refresh_rate_drop_between_ticks_lowers_the_limit() {
+        #[derive(Resource)]
+        struct Reported(Option<Duration>);
+        fn report(
+            reported: Res<Reported>,
+            mut detected: ResMut<DetectedFrametime>,
+            mut refresh_changed: EventWriter<RefreshRateChanged>,
+            mut lost: Local<bool>,
+        ) {
+            update_detected_frametime(reported.0, &mut detected, &mut lost, &mut refresh_changed);
+        }
+
+        let hz_144 = Duration::from_secs_f64(1.0 / 144.0);
+        let hz_60 = Duration::from_secs_f64(1.0 / 60.0);
+        let mut app = App::new();
+        init_refresh_rate_resources(app.world_mut(), Limiter::Auto, None);
+        app.insert_resource(Reported(Some(hz_144)))
+            .add_event::<RefreshRateChanged>()
+            .add_systems(Update, (report, get_display_refresh_rate).chain());
+        let frame_limit = app.world().resource::<FrametimeLimit>().clone();
+
+        app.update();
+        assert_eq!(frame_limit.current(), hz_144);
+        // E.g. a laptop lowering the panel refresh rate on battery, without any window event.
+        app.world_mut().resource_mut::<Reported>().0 = Some(hz_60);
+        app.update();
+        assert_eq!(frame_limit.current(), hz_60);
+        let events: Vec<_> = app
+            .world_mut()
+            .resource_mut::<Events<RefreshRateChanged>>()
+            .drain()
+            .map(|event| event.frametime)
+            .collect();
+        assert_eq!(events, [hz_144, hz_60]);
+    }
+
+    #[test]
+    fn zero_limit_keeps_the_stats_sane() {
+        let mut world = limiter_world(Limiter::Manual(Duration::ZERO), Duration::ZERO);
+        for _ in 0..3 {
+            world.resource_mut::<FrameTimer>().sleep_end =
+                Instant::now() - Duration::from_millis(3);
+            world.run_system_once(framerate_limiter).unwrap();
+        }
+        let stats = world.resource::<FramePaceStats>();
+        assert!(world.resource::<FrameTimer>().warned_zero_limit);
+        assert_eq!(stats.target_frametime(), None);
+        assert!(stats.frametime() >= Duration::from_millis(3));
+        assert_eq!(stats.oversleep(), Duration::ZERO);
+        assert_eq!(stats.undersleep(), Duration::ZERO);
+        assert_eq!(stats.sleep_time(), Duration::ZERO);
+        assert!(!stats.unable_to_reach_target());
+    }
+
+    #[test]
+    fn future_timer_measures_a_zero_frame() {
+        let limit = Duration::from_millis(1);
+        let mut world = limiter_world(Limiter::Manual(limit), limit);
+        world.resource_mut::<FrameTimer>().sleep_end = Instant::now() + Duration::from_secs(60);
+        let start = Instant::now();
+        world.run_system_once(framerate_limiter).unwrap();
+        // The limiter sleeps for at most one target, not until the timer ends.
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let stats = world.resource::<FramePaceStats>();
+        assert!(stats.frametime() < Duration::from_millis(1));
+        assert!(stats.oversleep() < Duration::from_secs(1));
+        assert!(world.resource::<FrameTimer>().sleep_end <= Instant::now());
+    }
+
+    #[cfg(feature = "winit")]
+    #[test]
+    fn detection_recovers_after_the_windows_are_gone() {
+        let mut world = World::new();
+        world.init_resource::<DetectedFrametime>();
+        world.init_resource::<Events<RefreshRateChanged>>();
+        let detection = world.register_system(detect);
+        let mut run = |frametime: Option<Duration>| {
+            world.run_system_with_input(detection, frametime).unwrap();
+            let events = world
+                .resource_mut::<Events<RefreshRateChanged>>()
+                .drain()
+                .count();
+            (world.resource::<DetectedFrametime>().0, events)
+        };
+        let hz_60 = Duration::from_secs_f64(1.0 / 60.0);
+        assert_eq!(run(None), (None, 0));
+        assert_eq!(run(Some(hz_60)), (Some(hz_60), 1));
+        // All windows closed or moved off every monitor, the last frametime is kept.
+        assert_eq!(run(None), (Some(hz_60), 0));
+        assert_eq!(run(None), (Some(hz_60), 0));
+        // A window is back on a monitor, the recovery is reported even if the rate is the same.
+        assert_eq!(run(Some(hz_60)), (Some(hz_60), 1));
+        assert_eq!(run(Some(hz_60)), (Some(hz_60), 0));
+    }
+
+    #[test]
+    fn limiter_handle_is_set_from_another_thread() {
+        let mut app = framepace_app();
+        app.world_mut().resource_mut::<FramepaceSettings>().limiter = Limiter::Off;
+        let handle = app.world().resource::<LimiterHandle>().clone();
+        let limiter = |flip: u64| {
+            if flip.is_multiple_of(2) {
+                Limiter::Off
+            } else {
+                Limiter::Manual(Duration::from_micros(flip))
+            }
+        };
+        let setter = {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for flip in 1..=201 {
+                    handle.set(limiter(flip));
+                    std::thread::sleep(Duration::from_micros(100));
+                }
+            })
+        };
+        // Keep updating while the limiter is flipped, so requests race the frames applying them.
+        let start = Instant::now();
+        let mut updates = 0;
+        while !setter.is_finished() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            app.update();
+            updates += 1;
+        }
+        setter.join().unwrap();
+        app.update();
+        assert!(updates > 1);
+        let last = limiter(201);
+        assert_eq!(app.world().resource::<FramepaceSettings>().limiter, last);
+        assert_eq!(handle.get(), Some(last));
+    }
+
+    #[test]
+    fn refresh_rounding_adjusts_the_reported_rate() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(RefreshRounding::Down.framerate(60_000), 59.5));
+        assert!(close(RefreshRounding::Down.framerate(59_940), 59.44));
+        assert!(close(RefreshRounding::Nearest.framerate(59_940), 60.0));
+        assert!(close(RefreshRounding::Nearest.framerate(143_400), 143.0));
+        assert!(close(RefreshRounding::Exact.framerate(59_940), 59.94));
+        assert!(close(RefreshRounding::Exact.framerate(144_000), 144.0));
+        // Rates that round down to nothing are discarded by the detection.
+        assert!(RefreshRounding::Down.framerate(400) <= 0.0);
+    }
+
+    #[test]
+    fn max_frame_sleep_clamps_an_absurd_target() {
+        let limit = Duration::from_secs(3600);
+        let mut world = limiter_world(Limiter::Manual(limit), limit);
+        world
+            .resource::<FramepaceSettingsProxy>()
+            .set_config(ProxyConfig {
+                max_frame_sleep: Duration::from_millis(20),
+                ..Default::default()
+            });
+        let start = Instant::now();
+        world.run_system_once(framerate_limiter).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(world.resource::<FrameTimer>().warned_sleep_clamp);
+        assert_eq!(
+            world.resource::<FramePaceStats>().sleep_time(),
+            Duration::from_millis(20)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn snaps_to_the_nearest_integer_framerate() {
+        let fps = |fps: f64| Duration::from_secs_f64(1.0 / fps);
+        assert_eq!(snap_to_integer_fps(fps(59.94)), fps(60.0));
+        assert_eq!(snap_to_integer_fps(fps(143.6)), fps(144.0));
+        assert_eq!(snap_to_integer_fps(fps(29.4)), fps(29.0));
+        assert_eq!(snap_to_integer_fps(fps(60.0)), fps(60.0));
+        // Slower than 1 fps snaps to 1 fps.
+        assert_eq!(snap_to_integer_fps(Duration::from_secs(5)), fps(1.0));
+        assert_eq!(snap_to_integer_fps(Duration::ZERO), Duration::ZERO);
+        assert_eq!(
+            snap_to_integer_fps(Duration::from_nanos(1)),
+            Duration::from_nanos(1)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn hitch_compensation_is_limited() {
+        let limit = Duration::from_millis(20);
+        let compensation = Duration::from_millis(2);
+        let mut world = limiter_world(Limiter::Manual(limit), limit);
+        world
+            .resource::<FramepaceSettingsProxy>()
+            .set_config(ProxyConfig {
+                hitch_compensation_limit: Some(compensation),
+                ..Default::default()
+            });
+        world.resource_mut::<FrameTimer>().sleep_end = Instant::now() - Duration::from_millis(200);
+        world.run_system_once(framerate_limiter).unwrap();
+        let stats = world.resource::<FramePaceStats>().clone();
+        assert!(stats.oversleep() >= Duration::from_millis(150));
+
+        // The following frames only give up the capped compensation of their sleep.
+        for _ in 0..2 {
+            world.run_system_once(framerate_limiter).unwrap();
+            assert!(
+                stats.sleep_time() >= limit - compensation - Duration::from_millis(2),
+                "{:?}",
+                stats.sleep_time()
+            );
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn display_limiters_do_not_limit_before_the_first_detection() {
+        for display_limiter in [Limiter::Auto, Limiter::Consistent(Default::default())] {
+            let mut world = refresh_rate_world(display_limiter.clone(), None);
+            world.run_system_once(get_display_refresh_rate).unwrap();
+            let frame_limit = world.resource::<FrametimeLimit>().clone();
+            let stats = world.resource::<FramePaceStats>().clone();
+            assert!(stats.awaiting_detection());
+            assert_eq!(frame_limit.current(), Duration::ZERO);
+
+            let mut limiter = limiter_world(display_limiter.clone(), Duration::ZERO);
+            limiter.insert_resource(frame_limit.clone());
+            limiter.insert_resource(stats.clone());
+            for _ in 0..3 {
+                // Give the frames some work, which the consistent limiter would otherwise target.
+                std::thread::sleep(Duration::from_millis(2));
+                limiter.run_system_once(framerate_limiter).unwrap();
+            }
+            assert!(!limiter.resource::<FrameTimer>().warned_zero_limit);
+            assert_eq!(stats.target_frametime(), None, "{display_limiter}");
+            assert_eq!(stats.sleep_time(), Duration::ZERO, "{display_limiter}");
+
+            let hz_60 = Duration::from_secs_f64(1.0 / 60.0);
+            world.insert_resource(DetectedFrametime(Some(hz_60)));
+            world.run_system_once(get_display_refresh_rate).unwrap();
+            assert!(!stats.awaiting_detection());
+            assert_eq!(frame_limit.current(), hz_60);
+        }
+    }
+
+    #[test]
+    fn stats_update_when_off_only_if_enabled() {
+        for update_stats_when_off in [true, false] {
+            let mut world = limiter_world(Limiter::Off, Duration::ZERO);
+            world
+                .resource::<FramepaceSettingsProxy>()
+                .set_config(ProxyConfig {
+                    update_stats_when_off,
+                    ..Default::default()
+                });
+            for _ in 0..3 {
+                world.resource_mut::<FrameTimer>().sleep_end =
+                    Instant::now() - Duration::from_millis(3);
+                world.run_system_once(framerate_limiter).unwrap();
+            }
+            let stats = world.resource::<FramePaceStats>();
+            assert_eq!(stats.target_frametime(), None);
+            if update_stats_when_off {
+                assert_eq!(stats.measured_frames(), 3);
+                assert!(stats.frametime() >= Duration::from_millis(3));
+                assert!(stats.work_time() >= Duration::from_millis(3));
+            } else {
+                assert_eq!(stats.measured_frames(), 0);
+                assert_eq!(stats.frametime(), Duration::ZERO);
+                assert_eq!(stats.work_time(), Duration::ZERO);
+            }
+        }
+    }
+
+    #[test]
+    fn late_frames_realign_to_the_schedule() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        assert_eq!(last_boundary(start, ms(25), ms(10)), start + ms(20));
+        assert_eq!(last_boundary(start, ms(30), ms(10)), start + ms(30));
+        assert_eq!(last_boundary(start, ms(9), ms(10)), start);
+        assert_eq!(last_boundary(start, ms(25), Duration::ZERO), start + ms(25));
+        assert_eq!(
+            last_boundary(start, Duration::from_secs(1 << 40), Duration::from_nanos(1)),
+            start + Duration::from_secs(1 << 40)
+        );
+    }
+
+    #[test]
+    fn intermittent_long_frames_stay_on_the_schedule() {
+        let limit = Duration::from_millis(10);
+        let mut world = limiter_world(Limiter::Manual(limit), limit);
+        world
+            .resource::<FramepaceSettingsProxy>()
+            .set_config(ProxyConfig {
+                drop_late_frames: true,
+                ..Default::default()
+            });
+        let start = Instant::now() - Duration::from_millis(25);
+        world.resource_mut::<FrameTimer>().sleep_end = start;
+        world.run_system_once(framerate_limiter).unwrap();
+        // The late frame isn't delayed further, and the next one starts from the last boundary.
+        let stats = world.resource::<FramePaceStats>().clone();
+        assert_eq!(stats.sleep_time(), Duration::ZERO);
+        let sleep_end = world.resource::<FrameTimer>().sleep_end;
+        let periods = sleep_end.duration_since(start).as_nanos() as f64 / limit.as_nanos() as f64;
+        assert_eq!(periods.fract(), 0.0, "{periods}");
+        assert!(periods >= 2.0);
+
+        // The following on-time frames are paced, without compensating the lateness.
+        world.run_system_once(framerate_limiter).unwrap();
+        assert!(stats.sleep_time() > Duration::ZERO);
+        assert!(!world.resource::<FrameTimer>().realigned);
+    }
+
+    #[test]
+    fn holding_preview_native_only_changes_the_settings_once() {
+        #[derive(Resource, Default)]
+        struct Changed(Vec<bool>);
+        fn hold(mut settings: ResMut<FramepaceSettings>) {
+            FramepaceSettings::preview_native(&mut settings, true);
+        }
+        fn record(settings: Res<FramepaceSettings>, mut changed: ResMut<Changed>) {
+            changed.0.push(settings.is_changed());
+        }
+        let mut app = framepace_app();
+        app.init_resource::<Changed>().add_systems(
+            Update,
+            (hold, record).chain().before(update_proxy_resources),
+        );
+        for _ in 0..4 {
+            app.update();
+        }
+        assert!(app
+            .world()
+            .resource::<FramepaceSettings>()
+            .is_previewing_native());
+        assert_eq!(
+            app.world().resource::<Changed>().0,
+            [true, false, false, false]
+        );
+    }
+
+    #[cfg(feature = "asset")]
+    #[test]
+    fn added_assets_do_not_keep_loading() {
+        use bevy_asset::AssetApp;
+        #[derive(bevy_asset::Asset, bevy_reflect::TypePath)]
+        struct Blob;
+        let mut app = framepace_app();
+        app.add_plugins((
+            bevy_core::TaskPoolPlugin::default(),
+            bevy_asset::AssetPlugin::default(),
+            loading::FramepaceLoadingPlugin,
+        ))
+        .init_asset::<Blob>();
+        let handle = app
+            .world_mut()
+            .resource_mut::<bevy_asset::Assets<Blob>>()
+            .add(Blob);
+        app.world_mut()
+            .resource_mut::<loading::FramepaceLoading>()
+            .track(handle);
+        app.update();
+        assert!(!app
+            .world()
+            .resource::<loading::FramepaceLoading>()
+            .is_loading());
+        assert_ne!(
+            app.world().resource::<EffectiveState>().reason,
+            LimiterReason::Loading
+        );
+    }
+
+    #[cfg(feature = "framepace_debug")]
+    #[test]
+    fn fps_diagnostic_is_the_limited_framerate() {
+        let mut app = framepace_app();
+        app.add_plugins((
+            bevy_time::TimePlugin,
+            bevy_diagnostic::DiagnosticsPlugin,
+            debug::DiagnosticsPlugin {
+                frametime_units: debug::FrametimeUnits::Fps,
+                ..Default::default()
+            },
+        ));
+        app.finish();
+        app.cleanup();
+        app.world_mut().resource_mut::<FramepaceSettings>().limiter =
+            Limiter::Manual(Duration::from_millis(10));
+        for _ in 0..15 {
+            app.update();
+        }
+        let fps = app
+            .world()
+            .resource::<bevy_diagnostic::DiagnosticsStore>()
+            .get(&debug::DiagnosticsPlugin::FRAMEPACE_FRAMETIME)
+            .and_then(|diagnostic| diagnostic.value())
+            .unwrap();
+        assert!((50.0..=110.0).contains(&fps), "{fps}");
+    }
+
+    #[test]
+    fn defaults_use_the_platform_default_limiter() {
+        let platform = Limiter::platform_default();
+        assert_eq!(Limiter::default(), platform);
+        assert_eq!(FramepaceSettings::default().limiter, platform);
+        let state = EffectiveState::default();
+        assert_eq!(
+            (state.configured, state.applied),
+            (platform.clone(), platform)
+        );
+    }
+}
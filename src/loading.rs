@@ -0,0 +1,81 @@
+//! Runs the app uncapped while assets are loading, so loading screens finish as fast as possible,
+//! then restores the configured limiter. Requires the `asset` feature.
+//!
+//! ```ignore
+//! fn load_level(
+//!     asset_server: Res<AssetServer>,
+//!     mut loading: ResMut<FramepaceLoading>,
+//! ) {
+//!     loading.track(asset_server.load::<Scene>("level.glb#Scene0"));
+//! }
+//!
+//! app.add_plugins((FramepacePlugin, FramepaceLoadingPlugin))
+//!     .add_systems(Startup, load_level);
+//! ```
+
+use bevy_app::prelude::*;
+use bevy_asset::{AssetServer, RecursiveDependencyLoadState, UntypedHandle};
+use bevy_ecs::prelude::*;
+
+use crate::FramepaceSettings;
+
+/// Suspends limiting while the assets tracked by [`FramepaceLoading`] are loading. Add it after
+/// the [`FramepacePlugin`](crate::FramepacePlugin).
+#[derive(Debug, Clone, Default)]
+pub struct FramepaceLoadingPlugin;
+impl Plugin for FramepaceLoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FramepaceLoading>()
+            .add_systems(Update, update_loading);
+    }
+}
+
+/// The loads that suspend limiting, see [`FramepaceLoadingPlugin`].
+///
+/// While any tracked asset, or one of its dependencies, is still loading, or while
+/// [`FramepaceLoading::loading`] is set, frames are not limited, and the
+/// [`EffectiveState`](crate::EffectiveState) reports
+/// [`LimiterReason::Loading`](crate::LimiterReason::Loading). Assets are no longer tracked once
+/// they finished loading, failed to, or if they aren't loaded by the asset server at all, e.g.
+/// when added with `Assets::add`.
+#[derive(Debug, Clone, Default, Resource)]
+pub struct FramepaceLoading {
+    /// Suspends limiting while set, e.g. while the app is generating a level, regardless of the
+    /// tracked assets.
+    pub loading: bool,
+    handles: Vec<UntypedHandle>,
+}
+
+impl FramepaceLoading {
+    /// Suspends limiting until the asset of `handle`, and its dependencies, finished loading.
+    pub fn track(&mut self, handle: impl Into<UntypedHandle>) {
+        self.handles.push(handle.into());
+    }
+
+    /// Returns `true` if limiting is suspended for loading.
+    pub fn is_loading(&self) -> bool {
+        self.loading || !self.handles.is_empty()
+    }
+}
+
+fn update_loading(
+    asset_server: Res<AssetServer>,
+    mut loading: ResMut<FramepaceLoading>,
+    mut settings: ResMut<FramepaceSettings>,
+) {
+    if !loading.handles.is_empty() {
+        // Assets without a load state, such as ones added with `Assets::add`, aren't loading.
+        loading.handles.retain(|handle| {
+            matches!(
+                asset_server.get_recursive_dependency_load_state(handle.id()),
+                Some(RecursiveDependencyLoadState::Loading)
+            )
+        });
+    }
+    // Only mark the settings changed when loading starts or ends, to avoid triggering change
+    // detection every frame.
+    let is_loading = loading.is_loading();
+    if settings.loading != is_loading {
+        settings.loading = is_loading;
+    }
+}
@@ -0,0 +1,166 @@
+//! Switches limiters when the device is plugged in or unplugged, e.g. to drop to a battery saving
+//! cap as soon as a laptop is unplugged. Requires the `power` feature.
+//!
+//! This crate doesn't read the power state itself. Implement [`PowerStateSource`] with the
+//! platform API or crate of your choice, and insert it with a [`FramepacePowerSource`]:
+//!
+//! ```ignore
+//! struct Battery(battery::Manager);
+//!
+//! impl PowerStateSource for Battery {
+//!     fn power_source(&mut self) -> Option<PowerSource> {
+//!         // Query the platform here, this is called every frame.
+//!     }
+//! }
+//!
+//! app.add_plugins((FramepacePlugin, FramepacePowerPlugin))
+//!     .insert_resource(FramepacePowerSource::new(Battery(manager)));
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy_app::prelude::*;
+use bevy_ecs::prelude::*;
+use bevy_utils::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::DetectedFrametime;
+use crate::{FramepaceSettings, Limiter};
+
+/// Applies the limiters of [`FramepacePowerSettings`] when the [`PowerSource`] reported by the
+/// [`FramepacePowerSource`] changes, and sends a [`PowerSourceChanged`] event. Add it after the
+/// [`FramepacePlugin`](crate::FramepacePlugin).
+#[derive(Debug, Clone, Default)]
+pub struct FramepacePowerPlugin;
+impl Plugin for FramepacePowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FramepacePowerSettings>()
+            .add_event::<PowerSourceChanged>()
+            .add_systems(Update, update_power_source);
+    }
+}
+
+/// Where the device is getting its power from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    /// The device is plugged in.
+    Ac,
+    /// The device is running on battery.
+    Battery,
+}
+
+/// Reports the current [`PowerSource`], see the [module docs](self).
+pub trait PowerStateSource: Send + Sync + 'static {
+    /// Returns the current power source, or `None` if it is unknown. Called once per frame during
+    /// [`Update`], it should be fast, e.g. by caching a value updated by the platform.
+    fn power_source(&mut self) -> Option<PowerSource>;
+}
+
+/// The [`PowerStateSource`] read by the [`FramepacePowerPlugin`]. Without this resource, the
+/// limiter is never switched.
+#[derive(Clone, Resource)]
+pub struct FramepacePowerSource(pub Arc<Mutex<dyn PowerStateSource>>);
+
+impl FramepacePowerSource {
+    /// Reads the power state from the provided `source`.
+    pub fn new(source: impl PowerStateSource) -> Self {
+        FramepacePowerSource(Arc::new(Mutex::new(source)))
+    }
+}
+
+opaque_debug!(FramepacePowerSource);
+
+/// The limiters applied by the [`FramepacePowerPlugin`] on each power source transition.
+#[derive(Debug, Clone, Resource)]
+pub struct FramepacePowerSettings {
+    /// The limiter applied while plugged in. Defaults to [`Limiter::Auto`].
+    pub ac: Limiter,
+    /// The limiter applied while on battery, immediately after unplugging. Defaults to 30 fps.
+    pub battery: Limiter,
+    /// How long the framerate takes to ramp from the battery limiter to the AC limiter after
+    /// plugging in. The ramp is skipped if either limiter doesn't resolve to a frametime, see
+    /// [`Limiter::resolve`]. Defaults to [`Duration::ZERO`], which switches immediately.
+    pub ramp: Duration,
+}
+
+impl Default for FramepacePowerSettings {
+    fn default() -> Self {
+        FramepacePowerSettings {
+            ac: Limiter::Auto,
+            battery: Limiter::from_framerate(30.0),
+            ramp: Duration::ZERO,
+        }
+    }
+}
+
+/// Sent when the power source reported by the [`FramepacePowerSource`] changes, including once
+/// when it is first known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub struct PowerSourceChanged {
+    /// The previous power source, `None` for the first reading.
+    pub previous: Option<PowerSource>,
+    /// The new power source.
+    pub current: PowerSource,
+}
+
+/// A ramp from the battery to the AC frametime.
+#[derive(Debug, Clone, Copy)]
+struct Ramp {
+    start: Instant,
+    from: Duration,
+    to: Duration,
+}
+
+fn update_power_source(
+    source: Option<Res<FramepacePowerSource>>,
+    power: Res<FramepacePowerSettings>,
+    mut settings: ResMut<FramepaceSettings>,
+    mut changed: EventWriter<PowerSourceChanged>,
+    #[cfg(not(target_arch = "wasm32"))] detected: Res<DetectedFrametime>,
+    mut current: Local<Option<PowerSource>>,
+    mut ramp: Local<Option<Ramp>>,
+) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let detected = detected.0;
+    #[cfg(target_arch = "wasm32")]
+    let detected = None;
+
+    let reading =
+        source.and_then(|source| source.0.try_lock().ok().and_then(|mut s| s.power_source()));
+    if let Some(reading) = reading.filter(|reading| *current != Some(*reading)) {
+        changed.send(PowerSourceChanged {
+            previous: *current,
+            current: reading,
+        });
+        let ramp_from = current.and(power.battery.resolve(detected));
+        *current = Some(reading);
+        *ramp = None;
+        match (reading, ramp_from, power.ac.resolve(detected)) {
+            (PowerSource::Ac, Some(from), Some(to)) if !power.ramp.is_zero() => {
+                *ramp = Some(Ramp {
+                    start: Instant::now(),
+                    from,
+                    to,
+                });
+            }
+            (PowerSource::Ac, ..) => settings.limiter = power.ac.clone(),
+            (PowerSource::Battery, ..) => settings.limiter = power.battery.clone(),
+        }
+    }
+
+    if let Some(active) = *ramp {
+        let progress = active.start.elapsed().as_secs_f64() / power.ramp.as_secs_f64();
+        if progress >= 1.0 || power.ramp.is_zero() {
+            settings.limiter = power.ac.clone();
+            *ramp = None;
+        } else {
+            let from = active.from.as_secs_f64();
+            let to = active.to.as_secs_f64();
+            let frametime = Duration::from_secs_f64(from + (to - from) * progress);
+            settings.limiter = Limiter::Manual(frametime);
+        }
+    }
+}